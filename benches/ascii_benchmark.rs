@@ -0,0 +1,66 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::Rng;
+use vec_lib::ascii::{
+    eq_ignore_ascii_case, to_ascii_lowercase_in_place, to_ascii_uppercase_in_place, translate,
+};
+
+const LEN: usize = 1000000;
+
+fn naive_translate(data: &mut [u8], table: &[u8; 256]) {
+    for x in data.iter_mut() {
+        *x = table[*x as usize];
+    }
+}
+
+fn ascii_benchmark(criteria: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+
+    let lens: [usize; 4] = [1000, 10000, 100000, 1000000];
+
+    // printable text with many letters
+    let text: Vec<u8> = (0..LEN).map(|_| rng.gen_range(b' '..=b'~')).collect();
+    let upper = text.to_ascii_uppercase();
+    // the conversions do the same work whatever the case of the letters, so buf is reused
+    let mut buf = text.clone();
+    // a permutation, so that translating repeatedly keeps all byte values
+    let table: [u8; 256] = core::array::from_fn(|i| (i as u8).wrapping_mul(167).wrapping_add(13));
+
+    for len in lens {
+        let text = &text[..len];
+        let upper = &upper[..len];
+        let buf = &mut buf[..len];
+
+        criteria.bench_function(format!("vec-lib lowercase {}", len).as_str(), |criteria| {
+            criteria.iter(|| to_ascii_lowercase_in_place(black_box(&mut *buf)))
+        });
+        criteria.bench_function(format!("std lowercase {}", len).as_str(), |criteria| {
+            criteria.iter(|| black_box(&mut *buf).make_ascii_lowercase())
+        });
+
+        criteria.bench_function(format!("vec-lib uppercase {}", len).as_str(), |criteria| {
+            criteria.iter(|| to_ascii_uppercase_in_place(black_box(&mut *buf)))
+        });
+        criteria.bench_function(format!("std uppercase {}", len).as_str(), |criteria| {
+            criteria.iter(|| black_box(&mut *buf).make_ascii_uppercase())
+        });
+
+        criteria.bench_function(
+            format!("vec-lib eq_ignore_ascii_case {}", len).as_str(),
+            |criteria| criteria.iter(|| eq_ignore_ascii_case(black_box(text), black_box(upper))),
+        );
+        criteria.bench_function(
+            format!("std eq_ignore_ascii_case {}", len).as_str(),
+            |criteria| criteria.iter(|| black_box(text).eq_ignore_ascii_case(black_box(upper))),
+        );
+
+        criteria.bench_function(format!("vec-lib translate {}", len).as_str(), |criteria| {
+            criteria.iter(|| translate(black_box(&mut *buf), black_box(&table)))
+        });
+        criteria.bench_function(format!("naive translate {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_translate(black_box(&mut *buf), black_box(&table)))
+        });
+    }
+}
+
+criterion_group!(benches, ascii_benchmark);
+criterion_main!(benches);
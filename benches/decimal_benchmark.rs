@@ -0,0 +1,115 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::Rng;
+use std::fmt::Write;
+use vec_lib::decimal::{ascii2bin_i16, ascii2bin_i32, bin2ascii, bin2ascii_i16, bin2ascii_i32};
+use vec_lib::vectori128::Vec16c;
+
+const LEN: usize = 1000;
+
+fn std_format<T: std::fmt::Display>(x: &[T], width: usize) -> String {
+    let mut out = String::with_capacity(x.len() * (width + 1));
+    for n in x {
+        write!(out, "{:>w$},", n, w = width).unwrap();
+    }
+    out
+}
+
+fn std_parse<T: std::str::FromStr, const N: usize>(text: &[u8], width: usize) -> Option<[T; N]> {
+    let mut fields = text.chunks_exact(width + 1);
+    let mut x = [(); N].map(|_| None);
+    for v in x.iter_mut() {
+        let field = std::str::from_utf8(&fields.next()?[..width]).ok()?;
+        *v = Some(field.trim_start().parse().ok()?);
+    }
+    Some(x.map(Option::unwrap))
+}
+
+fn decimal_benchmark(criteria: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+
+    // numbers of every length
+    let a8: Vec<[i8; 16]> = (0..LEN).map(|_| rng.gen()).collect();
+    let a16: Vec<[i16; 8]> = (0..LEN)
+        .map(|_| core::array::from_fn(|_| rng.gen::<i16>() >> rng.gen_range(0..16)))
+        .collect();
+    let a32: Vec<[i32; 4]> = (0..LEN)
+        .map(|_| core::array::from_fn(|_| rng.gen::<i32>() >> rng.gen_range(0..32)))
+        .collect();
+
+    criteria.bench_function("vec-lib bin2ascii", |criteria| {
+        criteria.iter(|| {
+            for x in &a8 {
+                black_box(bin2ascii(Vec16c::set_values(*x), 4, b' ', Some(b',')));
+            }
+        })
+    });
+    criteria.bench_function("std format i8", |criteria| {
+        criteria.iter(|| {
+            for x in &a8 {
+                black_box(std_format(x, 4));
+            }
+        })
+    });
+    criteria.bench_function("vec-lib bin2ascii_i16", |criteria| {
+        criteria.iter(|| {
+            for x in &a16 {
+                black_box(bin2ascii_i16(*x, 6, b' ', Some(b',')));
+            }
+        })
+    });
+    criteria.bench_function("std format i16", |criteria| {
+        criteria.iter(|| {
+            for x in &a16 {
+                black_box(std_format(x, 6));
+            }
+        })
+    });
+    criteria.bench_function("vec-lib bin2ascii_i32", |criteria| {
+        criteria.iter(|| {
+            for x in &a32 {
+                black_box(bin2ascii_i32(*x, 11, b' ', Some(b',')));
+            }
+        })
+    });
+    criteria.bench_function("std format i32", |criteria| {
+        criteria.iter(|| {
+            for x in &a32 {
+                black_box(std_format(x, 11));
+            }
+        })
+    });
+
+    let t16: Vec<String> = a16.iter().map(|x| std_format(x, 6)).collect();
+    let t32: Vec<String> = a32.iter().map(|x| std_format(x, 11)).collect();
+    criteria.bench_function("vec-lib ascii2bin_i16", |criteria| {
+        criteria.iter(|| {
+            for t in &t16 {
+                black_box(ascii2bin_i16(t.as_bytes(), 6, Some(b',')).unwrap());
+            }
+        })
+    });
+    criteria.bench_function("std parse i16", |criteria| {
+        criteria.iter(|| {
+            for t in &t16 {
+                black_box(std_parse::<i16, 8>(t.as_bytes(), 6).unwrap());
+            }
+        })
+    });
+    criteria.bench_function("vec-lib ascii2bin_i32", |criteria| {
+        criteria.iter(|| {
+            for t in &t32 {
+                black_box(ascii2bin_i32(t.as_bytes(), 11, Some(b',')).unwrap());
+            }
+        })
+    });
+    criteria.bench_function("std parse i32", |criteria| {
+        criteria.iter(|| {
+            for t in &t32 {
+                black_box(std_parse::<i32, 4>(t.as_bytes(), 11).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, decimal_benchmark);
+criterion_main!(benches);
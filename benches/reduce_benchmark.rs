@@ -0,0 +1,169 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::Rng;
+use vec_lib::reduce::{argmax_f32, argmax_i16, argmax_i32, argmax_i8, count_eq};
+use vec_lib::reduce::{max_i32, max_i8, min_f32, min_i16, min_i8};
+use vec_lib::reduce::{sum_f32, sum_i16, sum_i32, sum_i8};
+
+const LEN: usize = 1000000;
+
+fn naive_sum(a: &[i8]) -> i64 {
+    a.iter().map(|&x| x as i64).sum()
+}
+
+fn naive_min<T: Ord + Copy>(a: &[T]) -> Option<T> {
+    a.iter().copied().min()
+}
+
+fn naive_max<T: Ord + Copy>(a: &[T]) -> Option<T> {
+    a.iter().copied().max()
+}
+
+fn naive_argmax<T: Ord + Copy>(a: &[T]) -> Option<usize> {
+    let m = a.iter().copied().max()?;
+    a.iter().position(|&x| x == m)
+}
+
+fn naive_sum_i16(a: &[i16]) -> i64 {
+    a.iter().map(|&x| x as i64).sum()
+}
+
+fn naive_sum_i32(a: &[i32]) -> i64 {
+    a.iter().map(|&x| x as i64).sum()
+}
+
+fn naive_sum_f32(a: &[f32]) -> f32 {
+    a.iter().sum()
+}
+
+fn naive_min_f32(a: &[f32]) -> Option<f32> {
+    a.iter().copied().filter(|x| !x.is_nan()).reduce(f32::min)
+}
+
+fn naive_argmax_f32(a: &[f32]) -> Option<usize> {
+    let m = a.iter().copied().filter(|x| !x.is_nan()).reduce(f32::max)?;
+    a.iter().position(|&x| x == m)
+}
+
+fn naive_count_eq(a: &[u8], value: u8) -> usize {
+    a.iter().filter(|&&x| x == value).count()
+}
+
+fn reduce_benchmark(criteria: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+
+    let lens: [usize; 4] = [1000, 10000, 100000, 1000000];
+
+    let a: Vec<i8> = (0..LEN).map(|_| rng.gen()).collect();
+    let u: Vec<u8> = (0..LEN).map(|_| rng.gen()).collect();
+    let s: Vec<i16> = (0..LEN).map(|_| rng.gen()).collect();
+    let w: Vec<i32> = (0..LEN).map(|_| rng.gen()).collect();
+    let f: Vec<f32> = (0..LEN).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+    for len in lens {
+        let a = &a[..len];
+        let u = &u[..len];
+        let s = &s[..len];
+        let w = &w[..len];
+        let f = &f[..len];
+
+        criteria.bench_function(format!("vec-lib sum_i8 {}", len).as_str(), |criteria| {
+            criteria.iter(|| sum_i8(black_box(a)))
+        });
+        criteria.bench_function(format!("naive sum_i8 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_sum(black_box(a)))
+        });
+
+        criteria.bench_function(format!("vec-lib min_i8 {}", len).as_str(), |criteria| {
+            criteria.iter(|| min_i8(black_box(a)))
+        });
+        criteria.bench_function(format!("naive min_i8 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_min(black_box(a)))
+        });
+
+        criteria.bench_function(format!("vec-lib max_i8 {}", len).as_str(), |criteria| {
+            criteria.iter(|| max_i8(black_box(a)))
+        });
+        criteria.bench_function(format!("naive max_i8 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_max(black_box(a)))
+        });
+
+        criteria.bench_function(format!("vec-lib argmax_i8 {}", len).as_str(), |criteria| {
+            criteria.iter(|| argmax_i8(black_box(a)))
+        });
+        criteria.bench_function(format!("naive argmax_i8 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_argmax(black_box(a)))
+        });
+
+        criteria.bench_function(format!("vec-lib count_eq {}", len).as_str(), |criteria| {
+            criteria.iter(|| count_eq(black_box(u), black_box(42)))
+        });
+        criteria.bench_function(format!("naive count_eq {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_count_eq(black_box(u), black_box(42)))
+        });
+        criteria.bench_function(format!("vec-lib sum_i16 {}", len).as_str(), |criteria| {
+            criteria.iter(|| sum_i16(black_box(s)))
+        });
+        criteria.bench_function(format!("naive sum_i16 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_sum_i16(black_box(s)))
+        });
+
+        criteria.bench_function(format!("vec-lib min_i16 {}", len).as_str(), |criteria| {
+            criteria.iter(|| min_i16(black_box(s)))
+        });
+        criteria.bench_function(format!("naive min_i16 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_min(black_box(s)))
+        });
+
+        criteria.bench_function(format!("vec-lib argmax_i16 {}", len).as_str(), |criteria| {
+            criteria.iter(|| argmax_i16(black_box(s)))
+        });
+        criteria.bench_function(format!("naive argmax_i16 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_argmax(black_box(s)))
+        });
+
+        criteria.bench_function(format!("vec-lib sum_i32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| sum_i32(black_box(w)))
+        });
+        criteria.bench_function(format!("naive sum_i32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_sum_i32(black_box(w)))
+        });
+
+        criteria.bench_function(format!("vec-lib max_i32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| max_i32(black_box(w)))
+        });
+        criteria.bench_function(format!("naive max_i32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_max(black_box(w)))
+        });
+
+        criteria.bench_function(format!("vec-lib argmax_i32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| argmax_i32(black_box(w)))
+        });
+        criteria.bench_function(format!("naive argmax_i32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_argmax(black_box(w)))
+        });
+
+        criteria.bench_function(format!("vec-lib sum_f32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| sum_f32(black_box(f)))
+        });
+        criteria.bench_function(format!("naive sum_f32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_sum_f32(black_box(f)))
+        });
+
+        criteria.bench_function(format!("vec-lib min_f32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| min_f32(black_box(f)))
+        });
+        criteria.bench_function(format!("naive min_f32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_min_f32(black_box(f)))
+        });
+
+        criteria.bench_function(format!("vec-lib argmax_f32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| argmax_f32(black_box(f)))
+        });
+        criteria.bench_function(format!("naive argmax_f32 {}", len).as_str(), |criteria| {
+            criteria.iter(|| naive_argmax_f32(black_box(f)))
+        });
+    }
+}
+
+criterion_group!(benches, reduce_benchmark);
+criterion_main!(benches);
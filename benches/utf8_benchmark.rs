@@ -0,0 +1,48 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::Rng;
+use vec_lib::utf8::validate_utf8;
+
+const LEN: usize = 1000000;
+
+fn utf8_benchmark(criteria: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+
+    let lens: [usize; 4] = [1000, 10000, 100000, 1000000];
+
+    let ascii: Vec<u8> = (0..LEN).map(|_| rng.gen_range(b' '..=b'~')).collect();
+    // characters of every length
+    let mixed: String = (0..LEN)
+        .map(|_| match rng.gen_range(0..4) {
+            0 => rng.gen_range('\u{20}'..='\u{7E}'),
+            1 => rng.gen_range('\u{80}'..='\u{7FF}'),
+            2 => rng.gen_range('\u{800}'..='\u{FFFF}'),
+            _ => rng.gen_range('\u{10000}'..='\u{10FFFF}'),
+        })
+        .collect();
+
+    for len in lens {
+        let ascii = &ascii[..len];
+        // cut at a character boundary
+        let end = (len..=len + 3)
+            .find(|&i| mixed.is_char_boundary(i))
+            .unwrap();
+        let mixed = &mixed.as_bytes()[..end];
+
+        criteria.bench_function(format!("vec-lib utf8 ascii {}", len).as_str(), |criteria| {
+            criteria.iter(|| validate_utf8(black_box(ascii)))
+        });
+        criteria.bench_function(format!("core utf8 ascii {}", len).as_str(), |criteria| {
+            criteria.iter(|| core::str::from_utf8(black_box(ascii)).is_ok())
+        });
+
+        criteria.bench_function(format!("vec-lib utf8 mixed {}", len).as_str(), |criteria| {
+            criteria.iter(|| validate_utf8(black_box(mixed)))
+        });
+        criteria.bench_function(format!("core utf8 mixed {}", len).as_str(), |criteria| {
+            criteria.iter(|| core::str::from_utf8(black_box(mixed)).is_ok())
+        });
+    }
+}
+
+criterion_group!(benches, utf8_benchmark);
+criterion_main!(benches);
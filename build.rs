@@ -1,14 +1,22 @@
 use cfg_aliases::cfg_aliases;
 
 fn main() {
-    cxx_build::bridge("benches/vec16c_benchmark.rs")
-        .file("benches/vcl_benchmark.cpp")
-        .flag("-march=native")
-        .opt_level(3)
-        .std("c++17")
-        .compile("vcl_benchmark");
+    // the VCL comparison benchmark is x86 only. Skip it when cross compiling for other targets,
+    // and for targets without an operating system, such as x86_64-unknown-none
+    let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH");
+    let target_os = std::env::var("CARGO_CFG_TARGET_OS");
+    if target_arch.as_deref() == Ok("x86_64") && target_os.as_deref() != Ok("none") {
+        cxx_build::bridge("benches/vec16c_benchmark.rs")
+            .file("benches/vcl_benchmark.cpp")
+            .flag("-march=native")
+            .opt_level(3)
+            .std("c++17")
+            .compile("vcl_benchmark");
+    }
 
     println!("cargo:rerun-if-changed=benches/vec16c_benchmark.rs");
+    // --cfg vec_lib_scalar selects the portable backend
+    println!("cargo:rustc-check-cfg=cfg(vec_lib_scalar)");
 
     cfg_aliases! {
         linux: { target_os = "linux" },
@@ -0,0 +1,80 @@
+//! Uses a little of each part of vec-lib, so that it is all compiled for the no_std target.
+//! The modules that need SSE are only used when the target has it
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use vec_lib::simd::SimdInt;
+use vec_lib::vectori128::{horizontal_add, Vec16c};
+
+/// Sum of the elements of a + b
+pub fn sum(a: [i8; 16], b: [i8; 16]) -> i32 {
+    horizontal_add(Vec16c::from_array(a) + Vec16c::from_array(b))
+}
+
+/// Largest element of the elementwise maximum
+pub fn max(a: [i8; 16], b: [i8; 16]) -> i8 {
+    Vec16c::from_array(a)
+        .max(Vec16c::from_array(b))
+        .horizontal_max()
+}
+
+/// Parse a vector written as "[1, 2, ...]"
+pub fn parse(s: &str) -> Option<[i8; 16]> {
+    s.parse::<Vec16c>().ok().map(Vec16c::to_array)
+}
+
+/// Instruction set level for the dispatched functions
+#[cfg(target_arch = "x86_64")]
+pub fn level() -> i32 {
+    vec_lib::dispatch::dispatch_level().level()
+}
+
+/// Dot product with the sum, minimum and count of a
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+pub fn stats(a: &[i8], b: &[i8]) -> (i32, i64, Option<i8>, usize) {
+    let bytes = as_bytes(a);
+    (
+        vec_lib::dot::dot_i8(a, b),
+        vec_lib::reduce::sum_i8(a),
+        vec_lib::reduce::min_i8(a),
+        vec_lib::search::count_byte(bytes, 0),
+    )
+}
+
+/// The slice as bytes
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+fn as_bytes(a: &[i8]) -> &[u8] {
+    // SAFETY: u8 and i8 have the same layout
+    unsafe { core::slice::from_raw_parts(a.as_ptr() as *const u8, a.len()) }
+}
+
+/// Lower case copy of the text in buf, if it is valid UTF-8
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+pub fn lowercase(buf: &mut [u8]) -> Option<&str> {
+    vec_lib::utf8::validate_utf8(buf).ok()?;
+    vec_lib::ascii::to_ascii_lowercase_in_place(buf);
+    core::str::from_utf8(buf).ok()
+}
+
+/// Parse 16 numbers written with a width of 4 and separated by commas
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+pub fn parse_decimal(text: &[u8]) -> Option<[i8; 16]> {
+    vec_lib::decimal::ascii2bin(text, 4, Some(b','))
+        .ok()
+        .map(|a| a.to_array())
+}
+
+/// Hex and base64 of the input
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", feature = "alloc"))]
+pub fn encode(input: &[u8]) -> (alloc::string::String, alloc::string::String) {
+    use vec_lib::encoding::{base64_encode, hex_encode, Base64};
+    (hex_encode(input), base64_encode(input, Base64::STANDARD))
+}
+
+/// Position of the first byte that is not a letter or digit
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+pub fn first_symbol(text: &[u8]) -> Option<usize> {
+    vec_lib::strings::find_first_not_in_ranges(text, &[(b'0', b'9'), (b'A', b'Z'), (b'a', b'z')])
+}
@@ -0,0 +1,242 @@
+use crate::instrset::{has_avx512vbmi, instrset_detect, InstructionSet};
+use crate::vectori128::Vec16c;
+use core::arch::x86_64::*;
+
+// The case conversions use 256-bit vectors when the CPU has AVX2, and Vec16c otherwise
+
+/// Convert all bytes 'A'..='Z' to 'a'..='z', leaving other bytes unchanged.
+/// Same as <[u8]>::make_ascii_lowercase
+pub fn to_ascii_lowercase_in_place(data: &mut [u8]) {
+    if avx2() {
+        // SAFETY: the CPU has AVX2
+        unsafe { flip_case_avx2(data, b'A') }
+    } else {
+        map_in_place(data, lowercase);
+    }
+}
+
+/// Convert all bytes 'a'..='z' to 'A'..='Z', leaving other bytes unchanged.
+/// Same as <[u8]>::make_ascii_uppercase
+pub fn to_ascii_uppercase_in_place(data: &mut [u8]) {
+    if avx2() {
+        // SAFETY: the CPU has AVX2
+        unsafe { flip_case_avx2(data, b'a') }
+    } else {
+        map_in_place(data, uppercase);
+    }
+}
+
+/// Compare two byte strings, ignoring the case of ASCII letters.
+/// Same as <[u8]>::eq_ignore_ascii_case
+pub fn eq_ignore_ascii_case(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut va = Vec16c::new();
+    let mut vb = Vec16c::new();
+    let ca = a.chunks_exact(16);
+    let cb = b.chunks_exact(16);
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        // SAFETY: each chunk is 16 bytes long
+        unsafe {
+            va.load(x.as_ptr() as *const i8);
+            vb.load(y.as_ptr() as *const i8);
+        }
+        if !all_equal(lowercase(va), lowercase(vb)) {
+            return false;
+        }
+    }
+    ra.eq_ignore_ascii_case(rb)
+}
+
+/// Replace every byte x by table[x].
+///
+/// With AVX-512 VBMI, vpermi2b looks up 64 bytes at a time in the two 128-byte halves of the
+/// table. Otherwise this is a scalar loop. Looking up all 16 rows of the table with pshufb and
+/// selecting by the high nibble was measured with ascii_benchmark: it is no faster than the
+/// scalar loop with AVX2, and slower with SSSE3
+pub fn translate(data: &mut [u8], table: &[u8; 256]) {
+    if has_avx512vbmi() {
+        // SAFETY: the CPU has AVX-512 VBMI
+        unsafe { translate_vbmi(data, table) }
+    } else {
+        translate_scalar(data, table)
+    }
+}
+
+#[target_feature(enable = "avx512f,avx512bw,avx512vbmi")]
+unsafe fn translate_vbmi(data: &mut [u8], table: &[u8; 256]) {
+    let t: [__m512i; 4] =
+        core::array::from_fn(|i| _mm512_loadu_si512(table.as_ptr().add(64 * i) as *const _));
+    let mut chunks = data.chunks_exact_mut(64);
+    for chunk in &mut chunks {
+        let v = _mm512_loadu_si512(chunk.as_ptr() as *const _);
+        // bits 0..7 of each byte index a half of the table, and bit 7 selects the half
+        let lo = _mm512_permutex2var_epi8(t[0], v, t[1]);
+        let hi = _mm512_permutex2var_epi8(t[2], v, t[3]);
+        let r = _mm512_mask_blend_epi8(_mm512_movepi8_mask(v), lo, hi);
+        _mm512_storeu_si512(chunk.as_mut_ptr() as *mut _, r);
+    }
+    translate_scalar(chunks.into_remainder(), table);
+}
+
+fn translate_scalar(data: &mut [u8], table: &[u8; 256]) {
+    for x in data.iter_mut() {
+        *x = table[*x as usize];
+    }
+}
+
+/// The CPU has AVX2
+#[inline]
+fn avx2() -> bool {
+    instrset_detect() >= InstructionSet::Avx2
+}
+
+/// Set bit 5 (0x20) of bytes in 'A'..='Z'
+#[inline]
+fn lowercase(v: Vec16c) -> Vec16c {
+    flip_case(v, b'A')
+}
+
+/// Clear bit 5 (0x20) of bytes in 'a'..='z'
+#[inline]
+fn uppercase(v: Vec16c) -> Vec16c {
+    flip_case(v, b'a')
+}
+
+/// Flip bit 5 (0x20) of bytes in first..first + 26
+#[inline]
+fn flip_case(v: Vec16c, first: u8) -> Vec16c {
+    unsafe {
+        // shift the range to the bottom of the signed range so that one signed compare suffices
+        let shifted = _mm_add_epi8(v.xmm, _mm_set1_epi8((0x80 - first) as i8));
+        let letter = _mm_cmplt_epi8(shifted, _mm_set1_epi8((0x80 + 26u8) as i8));
+        let flip = _mm_and_si128(letter, _mm_set1_epi8(0x20));
+        Vec16c {
+            xmm: _mm_xor_si128(v.xmm, flip),
+        }
+    }
+}
+
+/// flip_case for all of data, 32 bytes at a time
+#[target_feature(enable = "avx2")]
+unsafe fn flip_case_avx2(data: &mut [u8], first: u8) {
+    let offset = _mm256_set1_epi8((0x80 - first) as i8);
+    let end = _mm256_set1_epi8((0x80 + 26u8) as i8);
+    let bit = _mm256_set1_epi8(0x20);
+    let mut chunks = data.chunks_exact_mut(32);
+    for chunk in &mut chunks {
+        let v = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+        let letter = _mm256_cmpgt_epi8(end, _mm256_add_epi8(v, offset));
+        let x = _mm256_xor_si256(v, _mm256_and_si256(letter, bit));
+        _mm256_storeu_si256(chunk.as_mut_ptr() as *mut __m256i, x);
+    }
+    map_in_place(chunks.into_remainder(), |v| flip_case(v, first));
+}
+
+#[inline]
+fn all_equal(a: Vec16c, b: Vec16c) -> bool {
+    unsafe { _mm_movemask_epi8(_mm_cmpeq_epi8(a.xmm, b.xmm)) == 0xFFFF }
+}
+
+/// Apply f to data in place, 16 bytes at a time. The last partial vector goes through a buffer.
+/// Always inlined, so that f is compiled with the target features of the caller
+#[inline(always)]
+fn map_in_place<F>(data: &mut [u8], f: F)
+where
+    F: Fn(Vec16c) -> Vec16c,
+{
+    let mut v = Vec16c::new();
+    let mut chunks = data.chunks_exact_mut(16);
+    for chunk in &mut chunks {
+        // SAFETY: each chunk is 16 bytes long
+        unsafe {
+            v.load(chunk.as_ptr() as *const i8);
+            f(v).store(chunk.as_mut_ptr() as *mut i8);
+        }
+    }
+    let rest = chunks.into_remainder();
+    if !rest.is_empty() {
+        let mut x: [u8; 16] = [0; 16];
+        x[..rest.len()].copy_from_slice(rest);
+        // SAFETY: x has room for 16 bytes
+        unsafe {
+            v.load(&x as *const u8 as *const i8);
+            f(v).store(&mut x as *mut u8 as *mut i8);
+        }
+        let n = rest.len();
+        rest.copy_from_slice(&x[..n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_ascii_case() {
+        let mut rng = rand::thread_rng();
+        let all: Vec<u8> = (0..=255).collect();
+
+        for len in 0..100 {
+            for _ in 0..20 {
+                // every byte value, and data dense in letters
+                let data: Vec<u8> = (0..len)
+                    .map(|_| match rng.gen_range(0..3) {
+                        0 => rng.gen(),
+                        1 => rng.gen_range(b'@'..=b'['),
+                        _ => rng.gen_range(b'`'..=b'{'),
+                    })
+                    .collect();
+                for data in [&data[..], &all[..len.min(256)]] {
+                    let mut lower = data.to_vec();
+                    to_ascii_lowercase_in_place(&mut lower);
+                    assert_eq!(lower, data.to_ascii_lowercase());
+                    let mut upper = data.to_vec();
+                    to_ascii_uppercase_in_place(&mut upper);
+                    assert_eq!(upper, data.to_ascii_uppercase());
+                    // the 128-bit version, which the functions above skip with AVX2
+                    let mut x = data.to_vec();
+                    map_in_place(&mut x, lowercase);
+                    assert_eq!(x, lower);
+                    map_in_place(&mut x, uppercase);
+                    assert_eq!(x, upper);
+
+                    assert!(eq_ignore_ascii_case(&lower, &upper));
+                    assert!(eq_ignore_ascii_case(data, &upper));
+                    if len > 0 {
+                        let mut other = data.to_vec();
+                        let i = rng.gen_range(0..len);
+                        other[i] = rng.gen();
+                        assert_eq!(
+                            eq_ignore_ascii_case(data, &other),
+                            data.eq_ignore_ascii_case(&other)
+                        );
+                        assert!(!eq_ignore_ascii_case(data, &other[1..]));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_translate() {
+        let mut rng = rand::thread_rng();
+        let all: Vec<u8> = (0..=255).collect();
+
+        for _ in 0..20 {
+            let table: [u8; 256] = core::array::from_fn(|_| rng.gen());
+            for len in [0, 1, 15, 16, 17, 63, 64, 65, 100, 256] {
+                let expect: Vec<u8> = all[..len].iter().map(|&x| table[x as usize]).collect();
+                let mut data = all[..len].to_vec();
+                translate(&mut data, &table);
+                assert_eq!(data, expect);
+                let mut data = all[..len].to_vec();
+                translate_scalar(&mut data, &table);
+                assert_eq!(data, expect);
+            }
+        }
+    }
+}
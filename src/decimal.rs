@@ -0,0 +1,793 @@
+use crate::vectori128::Vec16c;
+use core::arch::x86_64::*;
+use core::fmt;
+
+// bin2ascii and bin2hex return a String
+#[cfg(feature = "alloc")]
+use {
+    crate::vectori128::lookup16,
+    alloc::{string::String, vec::Vec},
+};
+
+/// Error returned by ascii2bin, ascii2bin_i16 and ascii2bin_i32
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The text is not one field of the given width per element
+    InvalidLength { length: usize },
+    /// The byte at position is not allowed there
+    InvalidByte { position: usize, byte: u8 },
+    /// The number in field does not fit in the element type
+    Overflow { field: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseError::InvalidLength { length } => write!(f, "invalid input length {}", length),
+            ParseError::InvalidByte { position, byte } => {
+                write!(f, "invalid byte {:#04x} at position {}", byte, position)
+            }
+            ParseError::Overflow { field } => write!(f, "number out of range in field {}", field),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Character written in place of a number that does not fit in its field
+pub const OVERFLOW_CHAR: u8 = b'*';
+
+/// Write the 16 elements of a as decimal numbers, right-aligned in fields of width characters.
+/// With pad b'0' the number is zero padded after the sign, otherwise pad is written before the
+/// sign. Each field is followed by separator, if any. A number that does not fit is written as
+/// width OVERFLOW_CHAR characters
+///
+/// Panics if pad or separator is not ASCII
+#[cfg(feature = "alloc")]
+pub fn bin2ascii(a: Vec16c, width: usize, pad: u8, separator: Option<u8>) -> String {
+    let (hundreds, tens, ones) = decimal_digits(a);
+    let negative = unsafe { _mm_cmplt_epi8(a.xmm, _mm_setzero_si128()) };
+    write_fields::<16, 3>(
+        negative,
+        [hundreds.xmm, tens.xmm, ones.xmm],
+        width,
+        pad,
+        separator,
+    )
+}
+
+/// Same as bin2ascii for eight 16-bit elements
+///
+/// Panics if pad or separator is not ASCII
+#[cfg(feature = "alloc")]
+pub fn bin2ascii_i16(a: [i16; 8], width: usize, pad: u8, separator: Option<u8>) -> String {
+    let (negative, digits) = decimal_digits_i16(a);
+    write_fields::<8, 5>(negative, digits, width, pad, separator)
+}
+
+/// Same as bin2ascii for four 32-bit elements
+///
+/// Panics if pad or separator is not ASCII
+#[cfg(feature = "alloc")]
+pub fn bin2ascii_i32(a: [i32; 4], width: usize, pad: u8, separator: Option<u8>) -> String {
+    let (negative, digits) = decimal_digits_i32(a);
+    write_fields::<4, 10>(negative, digits, width, pad, separator)
+}
+
+/// Write the 16 elements of a as two uppercase hexadecimal digits each, followed by
+/// separator, if any
+///
+/// Panics if separator is not ASCII
+#[cfg(feature = "alloc")]
+pub fn bin2hex(a: Vec16c, separator: Option<u8>) -> String {
+    write_hex(a, 1, separator)
+}
+
+/// Same as bin2hex for eight 16-bit elements, with four digits each
+///
+/// Panics if separator is not ASCII
+#[cfg(feature = "alloc")]
+pub fn bin2hex_i16(a: [i16; 8], separator: Option<u8>) -> String {
+    let bytes = core::array::from_fn(|i| a[i / 2].to_be_bytes()[i % 2] as i8);
+    write_hex(Vec16c::set_values(bytes), 2, separator)
+}
+
+/// Same as bin2hex for four 32-bit elements, with eight digits each
+///
+/// Panics if separator is not ASCII
+#[cfg(feature = "alloc")]
+pub fn bin2hex_i32(a: [i32; 4], separator: Option<u8>) -> String {
+    let bytes = core::array::from_fn(|i| a[i / 4].to_be_bytes()[i % 4] as i8);
+    write_hex(Vec16c::set_values(bytes), 4, separator)
+}
+
+/// Parse 16 fields of width characters, each followed by separator if any, as written by
+/// bin2ascii. A field holds optional leading spaces, an optional minus sign and at least one
+/// decimal digit
+pub fn ascii2bin(text: &[u8], width: usize, separator: Option<u8>) -> Result<Vec16c, ParseError> {
+    let x = parse_fields::<16>(text, width, separator, i8::MIN.into(), i8::MAX.into())?;
+    Ok(Vec16c::set_values(x.map(|v| v as i8)))
+}
+
+/// Same as ascii2bin for eight 16-bit elements
+pub fn ascii2bin_i16(
+    text: &[u8],
+    width: usize,
+    separator: Option<u8>,
+) -> Result<[i16; 8], ParseError> {
+    let x = parse_fields::<8>(text, width, separator, i16::MIN.into(), i16::MAX.into())?;
+    Ok(x.map(|v| v as i16))
+}
+
+/// Same as ascii2bin for four 32-bit elements
+pub fn ascii2bin_i32(
+    text: &[u8],
+    width: usize,
+    separator: Option<u8>,
+) -> Result<[i32; 4], ParseError> {
+    let x = parse_fields::<4>(text, width, separator, i32::MIN.into(), i32::MAX.into())?;
+    Ok(x.map(|v| v as i32))
+}
+
+/// Parse N fields as described for ascii2bin, with values in min..=max
+fn parse_fields<const N: usize>(
+    text: &[u8],
+    width: usize,
+    separator: Option<u8>,
+    min: i64,
+    max: i64,
+) -> Result<[i64; N], ParseError> {
+    let stride = width + separator.is_some() as usize;
+    if width == 0 || text.len() != N * stride {
+        return Err(ParseError::InvalidLength { length: text.len() });
+    }
+    if stride <= 16 {
+        // the number of digits of min, so that the values fit in the elements of parse_columns
+        let max_digits = (min.unsigned_abs().ilog10() + 1) as i8;
+        if let Some(x) = parse_columns::<N>(text, width, separator, max_digits) {
+            if x.iter().all(|v| (min..=max).contains(v)) {
+                return Ok(x);
+            }
+        }
+    }
+    // wide fields, and finding the error
+    parse_fields_scalar(text, width, separator, min, max)
+}
+
+/// Parse N fields of at most 16 bytes with the separator, with the fields transposed into
+/// columns as in write_fields. None if a field is invalid or has more than max_digits digits
+/// after its leading zeros
+#[inline]
+fn parse_columns<const N: usize>(
+    text: &[u8],
+    width: usize,
+    separator: Option<u8>,
+    max_digits: i8,
+) -> Option<[i64; N]> {
+    let stride = width + separator.is_some() as usize;
+    unsafe {
+        // 16 bytes per row. The rows near the end of text are copied to get the extra bytes
+        let rows = core::array::from_fn(|i| {
+            let start = i * stride;
+            if i >= N {
+                _mm_setzero_si128()
+            } else if start + 16 <= text.len() {
+                _mm_loadu_si128(text.as_ptr().add(start) as *const __m128i)
+            } else {
+                let mut row = [0u8; 16];
+                row[..text.len() - start].copy_from_slice(&text[start..]);
+                _mm_loadu_si128(row.as_ptr() as *const __m128i)
+            }
+        });
+        let columns = transpose(rows);
+
+        let zero = _mm_setzero_si128();
+        let ones = _mm_set1_epi8(-1);
+        let (mut started, mut negative, mut nonzero, mut invalid) = (zero, zero, zero, zero);
+        // minus the number of digits after the leading zeros
+        let mut count = zero;
+        let mut is_digit = zero;
+        let mut value = [zero; 2];
+        for &c in &columns[..width] {
+            let d = _mm_sub_epi8(c, _mm_set1_epi8(b'0' as i8));
+            is_digit = _mm_cmpeq_epi8(_mm_min_epu8(d, _mm_set1_epi8(9)), d);
+            let space = _mm_cmpeq_epi8(c, _mm_set1_epi8(b' ' as i8));
+            let sign = _mm_andnot_si128(started, _mm_cmpeq_epi8(c, _mm_set1_epi8(b'-' as i8)));
+            // spaces and one minus sign before the digits
+            let valid = _mm_or_si128(
+                is_digit,
+                _mm_or_si128(sign, _mm_andnot_si128(started, space)),
+            );
+            invalid = _mm_or_si128(invalid, _mm_andnot_si128(valid, ones));
+            started = _mm_or_si128(started, _mm_andnot_si128(space, ones));
+            negative = _mm_or_si128(negative, sign);
+            nonzero = _mm_or_si128(nonzero, _mm_andnot_si128(_mm_cmpeq_epi8(d, zero), is_digit));
+            count = _mm_add_epi8(count, nonzero);
+            value = add_digit::<N>(value, _mm_and_si128(d, is_digit));
+        }
+        // the field ends with a digit
+        invalid = _mm_or_si128(invalid, _mm_andnot_si128(is_digit, ones));
+        if let Some(sep) = separator {
+            let bad = _mm_cmpeq_epi8(columns[width], _mm_set1_epi8(sep as i8));
+            invalid = _mm_or_si128(invalid, _mm_andnot_si128(bad, ones));
+        }
+        let long = _mm_cmplt_epi8(count, _mm_set1_epi8(-max_digits));
+        if _mm_movemask_epi8(_mm_or_si128(invalid, long)) & ((1 << N) - 1) != 0 {
+            return None;
+        }
+
+        // the values are elements of 32 / N bytes
+        let mut x = [0u8; 32];
+        _mm_storeu_si128(x.as_mut_ptr() as *mut __m128i, value[0]);
+        _mm_storeu_si128(x.as_mut_ptr().add(16) as *mut __m128i, value[1]);
+        let negative = lanes(Vec16c { xmm: negative });
+        let size = 32 / N;
+        Some(core::array::from_fn(|i| {
+            let mut v = [0u8; 8];
+            v[..size].copy_from_slice(&x[i * size..(i + 1) * size]);
+            let v = i64::from_le_bytes(v);
+            if negative[i] != 0 {
+                -v
+            } else {
+                v
+            }
+        }))
+    }
+}
+
+/// value * 10 + d, where d holds one digit in each of the first N bytes and value holds the
+/// numbers in two vectors of 32 / N byte elements
+#[inline(always)]
+fn add_digit<const N: usize>(value: [__m128i; 2], d: __m128i) -> [__m128i; 2] {
+    unsafe {
+        let zero = _mm_setzero_si128();
+        match N {
+            16 => {
+                let d = [_mm_unpacklo_epi8(d, zero), _mm_unpackhi_epi8(d, zero)];
+                core::array::from_fn(|k| {
+                    let v = _mm_add_epi16(_mm_slli_epi16(value[k], 3), _mm_slli_epi16(value[k], 1));
+                    _mm_add_epi16(v, d[k])
+                })
+            }
+            8 => {
+                let d = _mm_unpacklo_epi8(d, zero);
+                let d = [_mm_unpacklo_epi16(d, zero), _mm_unpackhi_epi16(d, zero)];
+                core::array::from_fn(|k| {
+                    let v = _mm_add_epi32(_mm_slli_epi32(value[k], 3), _mm_slli_epi32(value[k], 1));
+                    _mm_add_epi32(v, d[k])
+                })
+            }
+            _ => {
+                let d = _mm_unpacklo_epi16(_mm_unpacklo_epi8(d, zero), zero);
+                let d = [_mm_unpacklo_epi32(d, zero), _mm_unpackhi_epi32(d, zero)];
+                core::array::from_fn(|k| {
+                    let v = _mm_add_epi64(_mm_slli_epi64(value[k], 3), _mm_slli_epi64(value[k], 1));
+                    _mm_add_epi64(v, d[k])
+                })
+            }
+        }
+    }
+}
+
+/// parse_fields one byte at a time
+fn parse_fields_scalar<const N: usize>(
+    text: &[u8],
+    width: usize,
+    separator: Option<u8>,
+    min: i64,
+    max: i64,
+) -> Result<[i64; N], ParseError> {
+    let stride = width + separator.is_some() as usize;
+    let invalid = |position: usize| ParseError::InvalidByte {
+        position,
+        byte: text[position],
+    };
+
+    let mut x: [i64; N] = [0; N];
+    for (i, field) in text.chunks_exact(stride).enumerate() {
+        let start = i * stride;
+        if let Some(sep) = separator {
+            if field[width] != sep {
+                return Err(invalid(start + width));
+            }
+        }
+        let spaces = field[..width].iter().take_while(|&&c| c == b' ').count();
+        let negative = field.get(spaces) == Some(&b'-');
+        let first = spaces + negative as usize;
+        if first == width {
+            return Err(invalid(start + width - 1));
+        }
+        let mut value: i64 = 0;
+        for (j, &c) in field[first..width].iter().enumerate() {
+            if !c.is_ascii_digit() {
+                return Err(invalid(start + first + j));
+            }
+            // saturate so that long numbers cannot wrap back into range
+            value = (10 * value + (c - b'0') as i64).min(1 - min);
+        }
+        let value = if negative { -value } else { value };
+        if !(min..=max).contains(&value) {
+            return Err(ParseError::Overflow { field: i });
+        }
+        x[i] = value;
+    }
+    Ok(x)
+}
+
+/// Write one field per element as described for bin2ascii. digits[d] holds decimal digit d of
+/// the absolute values, most significant first, and negative is -1 for the negative elements,
+/// with one byte per element. Only the first N bytes are used
+#[cfg(feature = "alloc")]
+fn write_fields<const N: usize, const D: usize>(
+    negative: __m128i,
+    digits: [__m128i; D],
+    width: usize,
+    pad: u8,
+    separator: Option<u8>,
+) -> String {
+    assert!(
+        pad.is_ascii() && separator.is_none_or(|s| s.is_ascii()),
+        "pad and separator must be ASCII"
+    );
+    let stride = width + separator.is_some() as usize;
+    if stride > 16 {
+        return write_fields_scalar::<N, D>(negative, digits, width, pad, separator);
+    }
+    // one row of 16 bytes per field. Each row is stored whole, and the bytes after the field
+    // are overwritten by the next row or cut off
+    let rows = transpose(field_columns(negative, digits, width, pad, separator));
+    let mut out: Vec<u8> = Vec::with_capacity((N - 1) * stride + 16);
+    // SAFETY: the capacity covers the 16 bytes stored at each row, and the first N * stride
+    // bytes are written
+    unsafe {
+        for (i, row) in rows[..N].iter().enumerate() {
+            _mm_storeu_si128(out.as_mut_ptr().add(i * stride) as *mut __m128i, *row);
+        }
+        out.set_len(N * stride);
+    }
+    String::from_utf8(out).unwrap()
+}
+
+/// The fields written by write_fields as 16 columns, with column p holding byte p of the field
+/// of each element. The columns after the field and separator are zero
+#[cfg(feature = "alloc")]
+#[inline]
+fn field_columns<const D: usize>(
+    negative: __m128i,
+    digits: [__m128i; D],
+    width: usize,
+    pad: u8,
+    separator: Option<u8>,
+) -> [__m128i; 16] {
+    unsafe {
+        let zero = _mm_setzero_si128();
+        let ones = _mm_set1_epi8(-1);
+        let select = |m: __m128i, a: __m128i, b: __m128i| {
+            _mm_or_si128(_mm_and_si128(m, a), _mm_andnot_si128(m, b))
+        };
+
+        // digit d is written where it is not a leading zero. The last digit always is
+        let mut significant = [ones; D];
+        let mut leading = ones;
+        for d in 0..D - 1 {
+            leading = _mm_and_si128(leading, _mm_cmpeq_epi8(digits[d], zero));
+            significant[d] = _mm_andnot_si128(leading, ones);
+        }
+        let significant = |d: isize| {
+            if d < 0 {
+                zero
+            } else {
+                significant[(d as usize).min(D - 1)]
+            }
+        };
+
+        // digit d goes in column d - first
+        let first = D as isize - width as isize;
+        let overflow = _mm_or_si128(
+            significant(first - 1),
+            _mm_and_si128(negative, significant(first)),
+        );
+        let mut columns = [zero; 16];
+        for (p, column) in columns[..width].iter_mut().enumerate() {
+            let d = first + p as isize;
+            let sign = if pad != b'0' {
+                _mm_andnot_si128(significant(d), significant(d + 1))
+            } else if p == 0 {
+                ones
+            } else {
+                zero
+            };
+            let mut c = select(
+                _mm_and_si128(negative, sign),
+                _mm_set1_epi8(b'-' as i8),
+                _mm_set1_epi8(pad as i8),
+            );
+            if (0..D as isize).contains(&d) {
+                let digit = _mm_add_epi8(digits[d as usize], _mm_set1_epi8(b'0' as i8));
+                c = select(significant(d), digit, c);
+            }
+            *column = select(overflow, _mm_set1_epi8(OVERFLOW_CHAR as i8), c);
+        }
+        if let Some(sep) = separator {
+            columns[width] = _mm_set1_epi8(sep as i8);
+        }
+        columns
+    }
+}
+
+/// write_fields one byte at a time, for fields of more than 16 bytes with the separator
+#[cfg(feature = "alloc")]
+fn write_fields_scalar<const N: usize, const D: usize>(
+    negative: __m128i,
+    digits: [__m128i; D],
+    width: usize,
+    pad: u8,
+    separator: Option<u8>,
+) -> String {
+    let negative = lanes(Vec16c { xmm: negative }).map(|x| x != 0);
+    let digits = digits.map(|d| lanes(Vec16c { xmm: d }).map(|x| x as u8));
+    let mut out = Vec::with_capacity(N * (width + separator.is_some() as usize));
+    for i in 0..N {
+        let skip = (0..D - 1).take_while(|&d| digits[d][i] == 0).count();
+        let len = D - skip + negative[i] as usize;
+        let number = (skip..D).map(|d| digits[d][i] + b'0');
+        if len > width {
+            out.extend(core::iter::repeat_n(OVERFLOW_CHAR, width));
+        } else if pad == b'0' {
+            if negative[i] {
+                out.push(b'-');
+            }
+            out.extend(core::iter::repeat_n(b'0', width - len));
+            out.extend(number);
+        } else {
+            out.extend(core::iter::repeat_n(pad, width - len));
+            if negative[i] {
+                out.push(b'-');
+            }
+            out.extend(number);
+        }
+        out.extend(separator);
+    }
+    String::from_utf8(out).unwrap()
+}
+
+/// Transpose a 16 by 16 byte matrix
+#[inline]
+fn transpose(a: [__m128i; 16]) -> [__m128i; 16] {
+    // Interleaving row j with row j + 8 into rows 2j and 2j + 1, in units of 1, 2, 4 and 8
+    // bytes, transposes the matrix with the bits of the column numbers reversed. The rows are
+    // put in bit reversed order first to undo that
+    let mut a: [__m128i; 16] = core::array::from_fn(|j| a[(j as u8).reverse_bits() as usize >> 4]);
+    unsafe {
+        for unit in 0..4 {
+            let b = a;
+            for j in 0..8 {
+                let (x, y) = (b[j], b[j + 8]);
+                (a[2 * j], a[2 * j + 1]) = match unit {
+                    0 => (_mm_unpacklo_epi8(x, y), _mm_unpackhi_epi8(x, y)),
+                    1 => (_mm_unpacklo_epi16(x, y), _mm_unpackhi_epi16(x, y)),
+                    2 => (_mm_unpacklo_epi32(x, y), _mm_unpackhi_epi32(x, y)),
+                    _ => (_mm_unpacklo_epi64(x, y), _mm_unpackhi_epi64(x, y)),
+                };
+            }
+        }
+    }
+    a
+}
+
+/// Write the bytes of a as hexadecimal digits, with separator after each group of bytes
+#[cfg(feature = "alloc")]
+fn write_hex(a: Vec16c, group: usize, separator: Option<u8>) -> String {
+    assert!(
+        separator.is_none_or(|s| s.is_ascii()),
+        "separator must be ASCII"
+    );
+    let digits = Vec16c::set_values(core::array::from_fn(|i| b"0123456789ABCDEF"[i] as i8));
+    let lo = lanes(lookup16(a, digits));
+    let hi = lanes(lookup16(
+        Vec16c {
+            xmm: unsafe { _mm_srli_epi16(a.xmm, 4) },
+        },
+        digits,
+    ));
+    let mut out = Vec::with_capacity(32 + (16 / group) * separator.is_some() as usize);
+    for i in 0..16 {
+        out.push(hi[i] as u8);
+        out.push(lo[i] as u8);
+        if i % group == group - 1 {
+            out.extend(separator);
+        }
+    }
+    String::from_utf8(out).unwrap()
+}
+
+fn lanes(a: Vec16c) -> [i8; 16] {
+    let mut x: [i8; 16] = [0; 16];
+    unsafe { a.store(&mut x as *mut i8) };
+    x
+}
+
+/// Decimal digits of the absolute values of the elements of a
+#[cfg(feature = "alloc")]
+#[inline]
+fn decimal_digits(a: Vec16c) -> (Vec16c, Vec16c, Vec16c) {
+    unsafe {
+        // absolute values as 16-bit numbers, 0..=128
+        let widen = |v: __m128i| {
+            let v = _mm_srai_epi16(v, 8);
+            _mm_max_epi16(v, _mm_sub_epi16(_mm_setzero_si128(), v))
+        };
+        let lo = widen(_mm_unpacklo_epi8(a.xmm, a.xmm));
+        let hi = widen(_mm_unpackhi_epi8(a.xmm, a.xmm));
+
+        // divide by 100 and by 10 with multiplies and shifts, exact in this range
+        let div100 = |v: __m128i| {
+            let q = _mm_srli_epi16(_mm_mullo_epi16(v, _mm_set1_epi16(41)), 12);
+            (q, _mm_sub_epi16(v, _mm_mullo_epi16(q, _mm_set1_epi16(100))))
+        };
+        let div10 = |v: __m128i| {
+            let q = _mm_srli_epi16(_mm_mullo_epi16(v, _mm_set1_epi16(205)), 11);
+            (q, _mm_sub_epi16(v, _mm_mullo_epi16(q, _mm_set1_epi16(10))))
+        };
+        let (h_lo, r_lo) = div100(lo);
+        let (h_hi, r_hi) = div100(hi);
+        let (t_lo, o_lo) = div10(r_lo);
+        let (t_hi, o_hi) = div10(r_hi);
+        (
+            Vec16c {
+                xmm: _mm_packus_epi16(h_lo, h_hi),
+            },
+            Vec16c {
+                xmm: _mm_packus_epi16(t_lo, t_hi),
+            },
+            Vec16c {
+                xmm: _mm_packus_epi16(o_lo, o_hi),
+            },
+        )
+    }
+}
+
+/// -1 in the bytes of the negative elements of a, and the decimal digits of the absolute
+/// values, most significant first, in the first 8 bytes
+#[cfg(feature = "alloc")]
+#[inline]
+fn decimal_digits_i16(a: [i16; 8]) -> (__m128i, [__m128i; 5]) {
+    unsafe {
+        // absolute values as unsigned 16-bit numbers, 0..=32768
+        let v = _mm_loadu_si128(a.as_ptr() as *const __m128i);
+        let sign = _mm_srai_epi16(v, 15);
+        let mut v = _mm_sub_epi16(_mm_xor_si128(v, sign), sign);
+
+        // x / 10 = (x * 52429) >> 19, exact for all 16-bit x
+        let mut digits = [_mm_setzero_si128(); 5];
+        for d in digits.iter_mut().rev() {
+            let q = _mm_srli_epi16(_mm_mulhi_epu16(v, _mm_set1_epi16(52429u16 as i16)), 3);
+            let r = _mm_sub_epi16(v, _mm_mullo_epi16(q, _mm_set1_epi16(10)));
+            *d = _mm_packus_epi16(r, r);
+            v = q;
+        }
+        (_mm_packs_epi16(sign, sign), digits)
+    }
+}
+
+/// -1 in the bytes of the negative elements of a, and the decimal digits of the absolute
+/// values, most significant first, in the first 4 bytes
+#[cfg(feature = "alloc")]
+#[inline]
+fn decimal_digits_i32(a: [i32; 4]) -> (__m128i, [__m128i; 10]) {
+    unsafe {
+        // absolute values as unsigned 32-bit numbers, 0..=2^31
+        let v = _mm_loadu_si128(a.as_ptr() as *const __m128i);
+        let sign = _mm_srai_epi32(v, 31);
+        let mut v = _mm_sub_epi32(_mm_xor_si128(v, sign), sign);
+
+        // x / 10 = (x * 0xCCCCCCCD) >> 35, exact for all 32-bit x. pmuludq multiplies the
+        // even elements, so the odd elements are shifted down and done separately
+        let m = _mm_set1_epi32(0xCCCCCCCDu32 as i32);
+        let mut digits = [_mm_setzero_si128(); 10];
+        for d in digits.iter_mut().rev() {
+            let even = _mm_srli_epi64(_mm_mul_epu32(v, m), 35);
+            let odd = _mm_srli_epi64(_mm_mul_epu32(_mm_srli_epi64(v, 32), m), 35);
+            let q = _mm_or_si128(even, _mm_slli_epi64(odd, 32));
+            let r = _mm_sub_epi32(v, _mm_add_epi32(_mm_slli_epi32(q, 3), _mm_add_epi32(q, q)));
+            let r = _mm_packs_epi32(r, r);
+            *d = _mm_packus_epi16(r, r);
+            v = q;
+        }
+        let sign = _mm_packs_epi32(sign, sign);
+        (_mm_packs_epi16(sign, sign), digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Display;
+    use rand::Rng;
+
+    /// The expected output of bin2ascii for x with pad b'0' and separator b',', and with
+    /// pad b' ' and no separator
+    fn expected_fields<T: Display>(x: &[T], width: usize) -> (String, String) {
+        let fit = |s: String| {
+            if s.len() > width {
+                "*".repeat(width)
+            } else {
+                s
+            }
+        };
+        let zero = x
+            .iter()
+            .map(|n| fit(format!("{:0w$}", n, w = width)) + ",")
+            .collect();
+        let space = x
+            .iter()
+            .map(|n| fit(format!("{:>w$}", n, w = width)))
+            .collect();
+        (zero, space)
+    }
+
+    #[test]
+    fn test_bin2ascii() {
+        // every value in every lane
+        for start in (-128..128).step_by(16) {
+            let x: [i8; 16] = core::array::from_fn(|i| (start + i as i32) as i8);
+            let a = Vec16c::set_values(x);
+            for width in 1..6 {
+                let (zero, space) = expected_fields(&x, width);
+                assert_eq!(bin2ascii(a, width, b'0', Some(b',')), zero);
+                assert_eq!(bin2ascii(a, width, b' ', None), space);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bin2hex() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            let x: [i8; 16] = rng.gen();
+            let expected: String = x.iter().map(|&n| format!("{:02X} ", n as u8)).collect();
+            assert_eq!(bin2hex(Vec16c::set_values(x), Some(b' ')), expected);
+        }
+    }
+
+    #[test]
+    fn test_bin2ascii_i16() {
+        // every 16-bit value, with a round trip through ascii2bin_i16
+        for start in (i16::MIN as i32..=i16::MAX as i32).step_by(8) {
+            let x: [i16; 8] = core::array::from_fn(|i| (start + i as i32) as i16);
+            for width in [1, 3, 5, 6, 7] {
+                let (zero, space) = expected_fields(&x, width);
+                assert_eq!(bin2ascii_i16(x, width, b'0', Some(b',')), zero);
+                assert_eq!(bin2ascii_i16(x, width, b' ', None), space);
+            }
+            let text = bin2ascii_i16(x, 6, b' ', Some(b';'));
+            assert_eq!(ascii2bin_i16(text.as_bytes(), 6, Some(b';')), Ok(x));
+            let hex: String = x.iter().map(|&n| format!("{:04X}", n as u16)).collect();
+            assert_eq!(bin2hex_i16(x, None), hex);
+        }
+    }
+
+    #[test]
+    fn test_bin2ascii_i32() {
+        let mut rng = rand::thread_rng();
+
+        for t in 0..10000 {
+            let x: [i32; 4] = match t {
+                0 => [i32::MIN, i32::MAX, 0, -1],
+                // numbers of every length
+                _ if t % 2 == 0 => {
+                    core::array::from_fn(|_| rng.gen::<i32>() >> rng.gen_range(0..32))
+                }
+                _ => rng.gen(),
+            };
+            // fields of up to 16 bytes are built in vectors, wider ones one byte at a time
+            for width in [1, 5, 10, 11, 12, 15, 16, 20] {
+                let (zero, space) = expected_fields(&x, width);
+                assert_eq!(bin2ascii_i32(x, width, b'0', Some(b',')), zero);
+                assert_eq!(bin2ascii_i32(x, width, b' ', None), space);
+            }
+            for pad in [b'0', b' '] {
+                for width in [11, 15, 16, 20] {
+                    let text = bin2ascii_i32(x, width, pad, Some(b','));
+                    assert_eq!(ascii2bin_i32(text.as_bytes(), width, Some(b',')), Ok(x));
+                }
+            }
+            let hex: String = x.iter().map(|&n| format!("{:08X} ", n as u32)).collect();
+            assert_eq!(bin2hex_i32(x, Some(b' ')), hex);
+        }
+
+        // numbers around the limits against str::parse
+        for _ in 0..10000 {
+            let n = rng.gen_range(-(1i64 << 33)..1i64 << 33) >> rng.gen_range(0..34);
+            let field = format!("{:>12}", n);
+            let expected = match field.trim_start().parse::<i32>() {
+                Ok(v) => Ok([v; 4]),
+                Err(_) => Err(ParseError::Overflow { field: 0 }),
+            };
+            assert_eq!(
+                ascii2bin_i32(field.repeat(4).as_bytes(), 12, None),
+                expected
+            );
+        }
+        assert_eq!(
+            ascii2bin_i16(&b" 32767|-32768| 32768|     0|".repeat(2), 6, Some(b'|')),
+            Err(ParseError::Overflow { field: 2 })
+        );
+    }
+
+    #[test]
+    fn test_transpose() {
+        let rows = core::array::from_fn(|i| {
+            Vec16c::set_values(core::array::from_fn(|j| (16 * i + j) as i8)).xmm
+        });
+        let columns = transpose(rows).map(|c| lanes(Vec16c { xmm: c }));
+        for (j, column) in columns.iter().enumerate() {
+            for (i, &x) in column.iter().enumerate() {
+                assert_eq!(x, (16 * i + j) as i8);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bin2ascii_non_ascii_pad() {
+        bin2ascii(Vec16c::new(), 4, 0xA0, None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bin2hex_non_ascii_separator() {
+        bin2hex_i32([0; 4], Some(0xFF));
+    }
+
+    #[test]
+    fn test_ascii2bin() {
+        let mut rng = rand::thread_rng();
+
+        // round trip through bin2ascii
+        for _ in 0..100 {
+            let x: [i8; 16] = rng.gen();
+            let a = Vec16c::set_values(x);
+            for pad in [b'0', b' '] {
+                let text = bin2ascii(a, 4, pad, Some(b';'));
+                assert_eq!(ascii2bin(text.as_bytes(), 4, Some(b';')), Ok(a));
+            }
+        }
+
+        // every number of up to 6 characters against str::parse
+        for n in -99999..=999999 {
+            let field = format!("{:>6}", n);
+            let text = field.repeat(16);
+            let expected = match field.trim_start().parse::<i8>() {
+                Ok(v) => Ok(Vec16c::set_value(v)),
+                Err(_) => Err(ParseError::Overflow { field: 0 }),
+            };
+            assert_eq!(ascii2bin(text.as_bytes(), 6, None), expected);
+        }
+
+        assert_eq!(
+            ascii2bin(b"1", 1, None),
+            Err(ParseError::InvalidLength { length: 1 })
+        );
+        let mut text = bin2ascii(Vec16c::set_value(-5), 3, b' ', Some(b',')).into_bytes();
+        text[22] = b'+';
+        assert_eq!(
+            ascii2bin(&text, 3, Some(b',')),
+            Err(ParseError::InvalidByte {
+                position: 22,
+                byte: b'+'
+            })
+        );
+        text[22] = b'-';
+        text[23] = b' ';
+        assert_eq!(
+            ascii2bin(&text, 3, Some(b',')),
+            Err(ParseError::InvalidByte {
+                position: 23,
+                byte: b' '
+            })
+        );
+    }
+}
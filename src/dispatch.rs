@@ -0,0 +1,212 @@
+use crate::instrset::{instrset_detect, InstructionSet};
+use core::sync::atomic::{AtomicI32, Ordering};
+
+/// Environment variable that limits the instruction set used by dispatched functions. It takes
+/// a VCL level number or one of sse2, sse3, ssse3, sse4.1, sse4.2, avx, avx2, avx512f and
+/// avx512. Other values are ignored. It is only read with the std feature
+pub const MAX_INSTRSET_VAR: &str = "VEC_LIB_MAX_INSTRSET";
+
+static LEVEL: AtomicI32 = AtomicI32::new(-1);
+
+/// The instruction set level that dispatched functions choose from: the detected level,
+/// lowered by MAX_INSTRSET_VAR if it is set. The result is cached after the first call
+pub fn dispatch_level() -> InstructionSet {
+    let mut level = LEVEL.load(Ordering::Relaxed);
+    if level < 0 {
+        let detected = instrset_detect();
+        level = limit().map_or(detected, |l| l.min(detected)).level();
+        LEVEL.store(level, Ordering::Relaxed);
+    }
+    InstructionSet::from_level(level).unwrap()
+}
+
+#[cfg(feature = "std")]
+fn limit() -> Option<InstructionSet> {
+    std::env::var(MAX_INSTRSET_VAR)
+        .ok()
+        .and_then(|s| parse_level(&s))
+}
+
+/// Without std there is no environment to read the limit from
+#[cfg(not(feature = "std"))]
+fn limit() -> Option<InstructionSet> {
+    None
+}
+
+#[cfg(feature = "std")]
+fn parse_level(s: &str) -> Option<InstructionSet> {
+    use InstructionSet::*;
+    let s = s.trim().to_ascii_lowercase();
+    if let Ok(level) = s.parse() {
+        return InstructionSet::from_level(level);
+    }
+    Some(match s.as_str() {
+        "sse2" => Sse2,
+        "sse3" => Sse3,
+        "ssse3" => Ssse3,
+        "sse4.1" => Sse41,
+        "sse4.2" => Sse42,
+        "avx" => Avx,
+        "avx2" => Avx2,
+        "avx512f" => Avx512F,
+        "avx512" => Avx512BwDqVl,
+        _ => return None,
+    })
+}
+
+/// Compile a function body for SSE2, SSE4.1, AVX2 and AVX-512 (F, BW, DQ, VL), and call the
+/// best version for dispatch_level(). The choice is made on the first call and the function
+/// pointer is cached, as in VCL's dispatch_example.
+///
+/// Each version is compiled with #[target_feature], which lets the compiler vectorize the
+/// body for that instruction set. Code selected with #[cfg(target_feature)], such as the
+/// vector classes, still follows the features of the whole build. Generic functions and
+/// argument patterns are not supported
+///
+/// ```
+/// vec_lib::multiversion! {
+///     /// Sum of squares
+///     pub fn sum_squares(x: &[i32]) -> i32 {
+///         x.iter().map(|v| v.wrapping_mul(*v)).fold(0, i32::wrapping_add)
+///     }
+/// }
+///
+/// assert_eq!(sum_squares(&[1, 2, 3]), 14);
+/// ```
+#[macro_export]
+macro_rules! multiversion {
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)? $body:block
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($($arg: $ty),*) $(-> $ret)? {
+            use ::core::sync::atomic::{AtomicPtr, Ordering};
+            use $crate::instrset::InstructionSet;
+
+            #[inline(always)]
+            fn generic($($arg: $ty),*) $(-> $ret)? $body
+
+            #[target_feature(enable = "avx512f,avx512bw,avx512dq,avx512vl")]
+            unsafe fn avx512($($arg: $ty),*) $(-> $ret)? {
+                generic($($arg),*)
+            }
+
+            #[target_feature(enable = "avx2")]
+            unsafe fn avx2($($arg: $ty),*) $(-> $ret)? {
+                generic($($arg),*)
+            }
+
+            #[target_feature(enable = "sse4.1")]
+            unsafe fn sse41($($arg: $ty),*) $(-> $ret)? {
+                generic($($arg),*)
+            }
+
+            #[target_feature(enable = "sse2")]
+            unsafe fn sse2($($arg: $ty),*) $(-> $ret)? {
+                generic($($arg),*)
+            }
+
+            type Kernel = unsafe fn($($ty),*) $(-> $ret)?;
+            static KERNEL: AtomicPtr<()> = AtomicPtr::new(::core::ptr::null_mut());
+
+            let mut kernel = KERNEL.load(Ordering::Relaxed);
+            if kernel.is_null() {
+                let level = $crate::dispatch::dispatch_level();
+                let best: Kernel = if level >= InstructionSet::Avx512BwDqVl {
+                    avx512
+                } else if level >= InstructionSet::Avx2 {
+                    avx2
+                } else if level >= InstructionSet::Sse41 {
+                    sse41
+                } else {
+                    sse2
+                };
+                kernel = best as *mut ();
+                KERNEL.store(kernel, Ordering::Relaxed);
+            }
+            // SAFETY: kernel is one of the versions above, and the processor supports it
+            unsafe { ::core::mem::transmute::<*mut (), Kernel>(kernel)($($arg),*) }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    crate::multiversion! {
+        fn sum_abs(x: &[i8]) -> i32 {
+            x.iter().map(|&v| (v as i32).abs()).sum()
+        }
+    }
+
+    crate::multiversion! {
+        fn add_in_place(x: &mut [i16], y: &[i16]) {
+            for (a, b) in x.iter_mut().zip(y) {
+                *a = a.wrapping_add(*b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_multiversion() {
+        let mut rng = rand::thread_rng();
+        if let Some(limit) = limit() {
+            assert_eq!(dispatch_level(), limit.min(instrset_detect()));
+        }
+
+        for len in 0..100 {
+            let x: Vec<i8> = (0..len).map(|_| rng.gen()).collect();
+            let y: Vec<i16> = (0..len).map(|_| rng.gen()).collect();
+            let expected: i32 = x.iter().map(|&v| (v as i32).abs()).sum();
+            assert_eq!(sum_abs(&x), expected);
+
+            let mut z: Vec<i16> = x.iter().map(|&v| v as i16).collect();
+            add_in_place(&mut z, &y);
+            for i in 0..len {
+                assert_eq!(z[i], (x[i] as i16).wrapping_add(y[i]));
+            }
+        }
+    }
+
+    /// Run test_multiversion again in a child process for each version that the processor
+    /// supports, with MAX_INSTRSET_VAR set so that the dispatcher picks that version
+    #[test]
+    fn test_multiversion_each_version() {
+        if std::env::var_os(MAX_INSTRSET_VAR).is_some() {
+            return;
+        }
+        let exe = std::env::current_exe().unwrap();
+        for name in ["sse2", "sse4.1", "avx2", "avx512"] {
+            if parse_level(name).unwrap() > instrset_detect() {
+                continue;
+            }
+            let output = std::process::Command::new(&exe)
+                .args(["--exact", "dispatch::tests::test_multiversion"])
+                .env(MAX_INSTRSET_VAR, name)
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(
+                output.status.success() && stdout.contains("1 passed"),
+                "test_multiversion with {}={}:\n{}{}",
+                MAX_INSTRSET_VAR,
+                name,
+                stdout,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    #[test]
+    fn test_dispatch_level() {
+        assert!(dispatch_level() <= instrset_detect());
+        assert_eq!(parse_level("AVX2"), Some(InstructionSet::Avx2));
+        assert_eq!(parse_level(" sse4.1 "), Some(InstructionSet::Sse41));
+        assert_eq!(parse_level("10"), Some(InstructionSet::Avx512BwDqVl));
+        assert_eq!(parse_level("1"), None);
+        assert_eq!(parse_level("neon"), None);
+    }
+}
@@ -0,0 +1,465 @@
+use crate::vectori128::Vec16c;
+use core::arch::x86_64::*;
+
+/// Dot product of two i8 slices, accumulated in i32. Overflow will wrap around
+///
+/// Panics if the slices have different lengths
+pub fn dot_i8(a: &[i8], b: &[i8]) -> i32 {
+    assert_eq!(a.len(), b.len(), "slices must have the same length");
+    #[cfg(target_feature = "avxvnni")]
+    unsafe {
+        dot_i8_vnni(a, b)
+    }
+    #[cfg(all(target_feature = "avx2", not(target_feature = "avxvnni")))]
+    unsafe {
+        dot_i8_avx2(a, b)
+    }
+    #[cfg(not(target_feature = "avx2"))]
+    dot_i8_sse(a, b)
+}
+
+/// Dot product of an u8 slice with an i8 slice, accumulated in i32. Overflow will wrap around
+///
+/// Panics if the slices have different lengths
+pub fn dot_u8i8(a: &[u8], b: &[i8]) -> i32 {
+    assert_eq!(a.len(), b.len(), "slices must have the same length");
+    #[cfg(target_feature = "avxvnni")]
+    unsafe {
+        dot_u8i8_vnni(a, b)
+    }
+    #[cfg(all(target_feature = "avx2", not(target_feature = "avxvnni")))]
+    unsafe {
+        dot_u8i8_avx2(a, b)
+    }
+    #[cfg(not(target_feature = "avx2"))]
+    dot_u8i8_sse(a, b)
+}
+
+/// Matrix product of row-major i8 matrices, c = a * b, with i32 results.
+/// a is m x k, b is k x n and c is m x n. Overflow will wrap around
+///
+/// Panics if a slice length does not match the dimensions
+pub fn gemm_i8(m: usize, n: usize, k: usize, a: &[i8], b: &[i8], c: &mut [i32]) {
+    assert_eq!(a.len(), m * k, "a must have m * k elements");
+    assert_eq!(b.len(), k * n, "b must have k * n elements");
+    assert_eq!(c.len(), m * n, "c must have m * n elements");
+
+    // blocks of 4 rows by 8 columns, leftover rows one at a time
+    for j in (0..n).step_by(8) {
+        let nb = (n - j).min(8);
+        let mut i = 0;
+        while i + 4 <= m {
+            let block = gemm_kernel::<4>(&a[i * k..(i + 4) * k], k, b, n, j, nb);
+            for (r, row) in block.iter().enumerate() {
+                c[(i + r) * n + j..(i + r) * n + j + nb].copy_from_slice(&row[..nb]);
+            }
+            i += 4;
+        }
+        while i < m {
+            let block = gemm_kernel::<1>(&a[i * k..(i + 1) * k], k, b, n, j, nb);
+            c[i * n + j..i * n + j + nb].copy_from_slice(&block[0][..nb]);
+            i += 1;
+        }
+    }
+}
+
+/// Multiply MR rows of a with columns j..j+nb of b, nb <= 8
+#[inline]
+fn gemm_kernel<const MR: usize>(
+    a: &[i8],
+    k: usize,
+    b: &[i8],
+    n: usize,
+    j: usize,
+    nb: usize,
+) -> [[i32; 8]; MR] {
+    #[cfg(target_feature = "avxvnni")]
+    unsafe {
+        gemm_kernel_vnni::<MR>(a, k, b, n, j, nb)
+    }
+    #[cfg(all(target_feature = "avx2", not(target_feature = "avxvnni")))]
+    unsafe {
+        gemm_kernel_avx2::<MR>(a, k, b, n, j, nb)
+    }
+    #[cfg(not(target_feature = "avx2"))]
+    gemm_kernel_sse::<MR>(a, k, b, n, j, nb)
+}
+
+/// Pairs of rows of b are interleaved so that pmaddwd adds two steps of k at a time
+#[cfg(not(target_feature = "avx2"))]
+fn gemm_kernel_sse<const MR: usize>(
+    a: &[i8],
+    k: usize,
+    b: &[i8],
+    n: usize,
+    j: usize,
+    nb: usize,
+) -> [[i32; 8]; MR] {
+    unsafe {
+        let mut acc = [[_mm_setzero_si128(); 2]; MR];
+        let mut p = 0;
+        while p < k {
+            let b0 = load_row8(&b[p * n + j..p * n + j + nb]);
+            let b1 = if p + 1 < k {
+                load_row8(&b[(p + 1) * n + j..(p + 1) * n + j + nb])
+            } else {
+                _mm_setzero_si128()
+            };
+            let lo = _mm_unpacklo_epi16(b0, b1);
+            let hi = _mm_unpackhi_epi16(b0, b1);
+            for (r, acc) in acc.iter_mut().enumerate() {
+                let pair = _mm_set1_epi32(pair_i16(&a[r * k..(r + 1) * k], p));
+                acc[0] = _mm_add_epi32(acc[0], _mm_madd_epi16(pair, lo));
+                acc[1] = _mm_add_epi32(acc[1], _mm_madd_epi16(pair, hi));
+            }
+            p += 2;
+        }
+
+        let mut result = [[0i32; 8]; MR];
+        for (row, acc) in result.iter_mut().zip(acc.iter()) {
+            _mm_storeu_si128(row.as_mut_ptr() as *mut __m128i, acc[0]);
+            _mm_storeu_si128(row.as_mut_ptr().add(4) as *mut __m128i, acc[1]);
+        }
+        result
+    }
+}
+
+/// Same as gemm_kernel_sse with all 8 columns in one vector, columns 0-3 in the low lane
+#[cfg(all(target_feature = "avx2", not(target_feature = "avxvnni")))]
+unsafe fn gemm_kernel_avx2<const MR: usize>(
+    a: &[i8],
+    k: usize,
+    b: &[i8],
+    n: usize,
+    j: usize,
+    nb: usize,
+) -> [[i32; 8]; MR] {
+    let mut acc = [_mm256_setzero_si256(); MR];
+    let mut p = 0;
+    while p < k {
+        let b0 = load_row8(&b[p * n + j..p * n + j + nb]);
+        let b1 = if p + 1 < k {
+            load_row8(&b[(p + 1) * n + j..(p + 1) * n + j + nb])
+        } else {
+            _mm_setzero_si128()
+        };
+        let bb = _mm256_set_m128i(_mm_unpackhi_epi16(b0, b1), _mm_unpacklo_epi16(b0, b1));
+        for (r, acc) in acc.iter_mut().enumerate() {
+            let pair = _mm256_set1_epi32(pair_i16(&a[r * k..(r + 1) * k], p));
+            *acc = _mm256_add_epi32(*acc, _mm256_madd_epi16(pair, bb));
+        }
+        p += 2;
+    }
+
+    let mut result = [[0i32; 8]; MR];
+    for (row, acc) in result.iter_mut().zip(acc.iter()) {
+        _mm256_storeu_si256(row.as_mut_ptr() as *mut __m256i, *acc);
+    }
+    result
+}
+
+/// Four rows of b are interleaved bytewise so that vpdpbusd adds four steps of k at a time.
+/// a + 128 is the unsigned operand, corrected as in dot_i8_vnni
+#[cfg(target_feature = "avxvnni")]
+unsafe fn gemm_kernel_vnni<const MR: usize>(
+    a: &[i8],
+    k: usize,
+    b: &[i8],
+    n: usize,
+    j: usize,
+    nb: usize,
+) -> [[i32; 8]; MR] {
+    let signbit = _mm256_set1_epi8(i8::MIN);
+    let mut acc = [_mm256_setzero_si256(); MR];
+    let mut corr = _mm256_setzero_si256();
+    let mut p = 0;
+    while p < k {
+        let row = |q: usize| {
+            if q < k {
+                load_bytes8(&b[q * n + j..q * n + j + nb])
+            } else {
+                _mm_setzero_si128()
+            }
+        };
+        let b01 = _mm_unpacklo_epi8(row(p), row(p + 1));
+        let b23 = _mm_unpacklo_epi8(row(p + 2), row(p + 3));
+        let bq = _mm256_set_m128i(_mm_unpackhi_epi16(b01, b23), _mm_unpacklo_epi16(b01, b23));
+        corr = _mm256_dpbusd_avx_epi32(corr, signbit, bq);
+        for (r, acc) in acc.iter_mut().enumerate() {
+            let mut quad = [0u8; 4];
+            for (q, x) in quad.iter_mut().enumerate() {
+                if p + q < k {
+                    *x = a[r * k + p + q] as u8;
+                }
+            }
+            let quad = _mm256_set1_epi32(i32::from_le_bytes(quad));
+            *acc = _mm256_dpbusd_avx_epi32(*acc, _mm256_xor_si256(quad, signbit), bq);
+        }
+        p += 4;
+    }
+
+    let mut result = [[0i32; 8]; MR];
+    for (row, acc) in result.iter_mut().zip(acc.iter()) {
+        let sum = _mm256_sub_epi32(*acc, corr);
+        _mm256_storeu_si256(row.as_mut_ptr() as *mut __m256i, sum);
+    }
+    result
+}
+
+/// Elements p and p+1 of a row of a as two i16 in one i32. Element p+1 is zero past the end
+#[cfg(not(target_feature = "avxvnni"))]
+#[inline]
+fn pair_i16(row: &[i8], p: usize) -> i32 {
+    let a0 = row[p] as i16 as u16 as u32;
+    let a1 = row.get(p + 1).map_or(0, |&x| x as i16 as u16 as u32);
+    (a0 | (a1 << 16)) as i32
+}
+
+/// Load up to 8 elements. Missing elements are zero
+#[inline]
+fn load_bytes8(x: &[i8]) -> __m128i {
+    unsafe {
+        if x.len() == 8 {
+            _mm_loadl_epi64(x.as_ptr() as *const __m128i)
+        } else {
+            let mut t: [i8; 8] = [0; 8];
+            t[..x.len()].copy_from_slice(x);
+            _mm_loadl_epi64(&t as *const i8 as *const __m128i)
+        }
+    }
+}
+
+/// Load up to 8 elements and sign-extend them to 16 bits. Missing elements are zero
+#[cfg(not(target_feature = "avxvnni"))]
+#[inline]
+fn load_row8(x: &[i8]) -> __m128i {
+    unsafe {
+        let v = load_bytes8(x);
+        _mm_srai_epi16(_mm_unpacklo_epi8(v, v), 8)
+    }
+}
+
+/// Load fewer than 16 elements, setting the rest to zero
+#[inline]
+fn load_tail(x: &[i8]) -> Vec16c {
+    let mut t: [i8; 16] = [0; 16];
+    t[..x.len()].copy_from_slice(x);
+    Vec16c::set_values(t)
+}
+
+/// Sum of the four i32 elements. Overflow will wrap around
+#[inline]
+fn horizontal_add_epi32(x: __m128i) -> i32 {
+    unsafe {
+        let sum1 = _mm_add_epi32(x, _mm_unpackhi_epi64(x, x));
+        let sum2 = _mm_add_epi32(sum1, _mm_shuffle_epi32(sum1, 1));
+        _mm_cvtsi128_si32(sum2)
+    }
+}
+
+/// Products of signed elements added pairwise into four i32
+#[inline]
+fn madd_i8_epi32(a: Vec16c, b: Vec16c) -> __m128i {
+    unsafe {
+        // unpack each byte into the high half of a 16-bit element, then shift down with sign
+        let alo = _mm_srai_epi16(_mm_unpacklo_epi8(a.xmm, a.xmm), 8);
+        let blo = _mm_srai_epi16(_mm_unpacklo_epi8(b.xmm, b.xmm), 8);
+        let ahi = _mm_srai_epi16(_mm_unpackhi_epi8(a.xmm, a.xmm), 8);
+        let bhi = _mm_srai_epi16(_mm_unpackhi_epi8(b.xmm, b.xmm), 8);
+        _mm_add_epi32(_mm_madd_epi16(alo, blo), _mm_madd_epi16(ahi, bhi))
+    }
+}
+
+/// Products of unsigned elements of a and signed elements of b added pairwise into four i32.
+/// pmaddubsw is not used because it saturates the pairwise sums
+#[inline]
+fn madd_u8i8_epi32(a: Vec16c, b: Vec16c) -> __m128i {
+    unsafe {
+        let zero = _mm_setzero_si128();
+        let alo = _mm_unpacklo_epi8(a.xmm, zero);
+        let blo = _mm_srai_epi16(_mm_unpacklo_epi8(b.xmm, b.xmm), 8);
+        let ahi = _mm_unpackhi_epi8(a.xmm, zero);
+        let bhi = _mm_srai_epi16(_mm_unpackhi_epi8(b.xmm, b.xmm), 8);
+        _mm_add_epi32(_mm_madd_epi16(alo, blo), _mm_madd_epi16(ahi, bhi))
+    }
+}
+
+fn dot_i8_sse(a: &[i8], b: &[i8]) -> i32 {
+    let mut acc = unsafe { _mm_setzero_si128() };
+    let mut va = Vec16c::new();
+    let mut vb = Vec16c::new();
+    let ca = a.chunks_exact(16);
+    let cb = b.chunks_exact(16);
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        // SAFETY: each chunk is 16 elements long
+        unsafe {
+            va.load(x.as_ptr());
+            vb.load(y.as_ptr());
+            acc = _mm_add_epi32(acc, madd_i8_epi32(va, vb));
+        }
+    }
+    if !ra.is_empty() {
+        acc = unsafe { _mm_add_epi32(acc, madd_i8_epi32(load_tail(ra), load_tail(rb))) };
+    }
+    horizontal_add_epi32(acc)
+}
+
+fn dot_u8i8_sse(a: &[u8], b: &[i8]) -> i32 {
+    let mut acc = unsafe { _mm_setzero_si128() };
+    let mut va = Vec16c::new();
+    let mut vb = Vec16c::new();
+    let ca = a.chunks_exact(16);
+    let cb = b.chunks_exact(16);
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        // SAFETY: each chunk is 16 elements long
+        unsafe {
+            va.load(x.as_ptr() as *const i8);
+            vb.load(y.as_ptr());
+            acc = _mm_add_epi32(acc, madd_u8i8_epi32(va, vb));
+        }
+    }
+    if !ra.is_empty() {
+        // SAFETY: u8 and i8 have the same layout
+        let ra = unsafe { core::slice::from_raw_parts(ra.as_ptr() as *const i8, ra.len()) };
+        acc = unsafe { _mm_add_epi32(acc, madd_u8i8_epi32(load_tail(ra), load_tail(rb))) };
+    }
+    horizontal_add_epi32(acc)
+}
+
+/// Sum of the eight i32 elements. Overflow will wrap around
+#[cfg(target_feature = "avx2")]
+#[inline]
+fn horizontal_add_epi32_256(x: __m256i) -> i32 {
+    unsafe {
+        let lo = _mm256_castsi256_si128(x);
+        let hi = _mm256_extracti128_si256(x, 1);
+        horizontal_add_epi32(_mm_add_epi32(lo, hi))
+    }
+}
+
+#[cfg(all(target_feature = "avx2", not(target_feature = "avxvnni")))]
+unsafe fn dot_i8_avx2(a: &[i8], b: &[i8]) -> i32 {
+    let mut acc = _mm256_setzero_si256();
+    let ca = a.chunks_exact(16);
+    let cb = b.chunks_exact(16);
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        let x = _mm256_cvtepi8_epi16(_mm_loadu_si128(x.as_ptr() as *const __m128i));
+        let y = _mm256_cvtepi8_epi16(_mm_loadu_si128(y.as_ptr() as *const __m128i));
+        acc = _mm256_add_epi32(acc, _mm256_madd_epi16(x, y));
+    }
+    horizontal_add_epi32_256(acc).wrapping_add(dot_i8_sse(ra, rb))
+}
+
+#[cfg(all(target_feature = "avx2", not(target_feature = "avxvnni")))]
+unsafe fn dot_u8i8_avx2(a: &[u8], b: &[i8]) -> i32 {
+    let mut acc = _mm256_setzero_si256();
+    let ca = a.chunks_exact(16);
+    let cb = b.chunks_exact(16);
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        let x = _mm256_cvtepu8_epi16(_mm_loadu_si128(x.as_ptr() as *const __m128i));
+        let y = _mm256_cvtepi8_epi16(_mm_loadu_si128(y.as_ptr() as *const __m128i));
+        acc = _mm256_add_epi32(acc, _mm256_madd_epi16(x, y));
+    }
+    horizontal_add_epi32_256(acc).wrapping_add(dot_u8i8_sse(ra, rb))
+}
+
+#[cfg(target_feature = "avxvnni")]
+unsafe fn dot_i8_vnni(a: &[i8], b: &[i8]) -> i32 {
+    // vpdpbusd multiplies unsigned by signed bytes. With a + 128 as the unsigned operand,
+    // a * b = (a + 128) * b - 128 * b
+    let signbit = _mm256_set1_epi8(i8::MIN);
+    let mut acc = _mm256_setzero_si256();
+    let mut corr = _mm256_setzero_si256();
+    let ca = a.chunks_exact(32);
+    let cb = b.chunks_exact(32);
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        let x = _mm256_loadu_si256(x.as_ptr() as *const __m256i);
+        let y = _mm256_loadu_si256(y.as_ptr() as *const __m256i);
+        acc = _mm256_dpbusd_avx_epi32(acc, _mm256_xor_si256(x, signbit), y);
+        corr = _mm256_dpbusd_avx_epi32(corr, signbit, y);
+    }
+    let sum = _mm256_sub_epi32(acc, corr);
+    horizontal_add_epi32_256(sum).wrapping_add(dot_i8_sse(ra, rb))
+}
+
+#[cfg(target_feature = "avxvnni")]
+unsafe fn dot_u8i8_vnni(a: &[u8], b: &[i8]) -> i32 {
+    let mut acc = _mm256_setzero_si256();
+    let ca = a.chunks_exact(32);
+    let cb = b.chunks_exact(32);
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        let x = _mm256_loadu_si256(x.as_ptr() as *const __m256i);
+        let y = _mm256_loadu_si256(y.as_ptr() as *const __m256i);
+        acc = _mm256_dpbusd_avx_epi32(acc, x, y);
+    }
+    horizontal_add_epi32_256(acc).wrapping_add(dot_u8i8_sse(ra, rb))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn dot_scalar<T: Copy + Into<i32>>(a: &[T], b: &[i8]) -> i32 {
+        a.iter()
+            .zip(b)
+            .fold(0i32, |s, (&x, &y)| s.wrapping_add(x.into() * y as i32))
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut rng = rand::thread_rng();
+
+        for len in 0..300 {
+            let a: Vec<i8> = (0..len).map(|_| rng.gen()).collect();
+            let u: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let b: Vec<i8> = (0..len).map(|_| rng.gen()).collect();
+            assert_eq!(dot_i8(&a, &b), dot_scalar(&a, &b), "dot_i8 len {}", len);
+            assert_eq!(dot_u8i8(&u, &b), dot_scalar(&u, &b), "dot_u8i8 len {}", len);
+        }
+
+        // extreme values, long enough to wrap around
+        let len = 300_001;
+        let a = vec![i8::MIN; len];
+        let u = vec![u8::MAX; len];
+        let b = vec![i8::MIN; len];
+        assert_eq!(dot_i8(&a, &b), dot_scalar(&a, &b));
+        assert_eq!(dot_u8i8(&u, &b), dot_scalar(&u, &b));
+    }
+
+    #[test]
+    fn test_gemm_i8() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..200 {
+            let m = rng.gen_range(0..12);
+            let n = rng.gen_range(0..20);
+            let k = rng.gen_range(0..40);
+            let a: Vec<i8> = (0..m * k).map(|_| rng.gen()).collect();
+            let b: Vec<i8> = (0..k * n).map(|_| rng.gen()).collect();
+            let mut c = vec![0; m * n];
+            gemm_i8(m, n, k, &a, &b, &mut c);
+            for i in 0..m {
+                for j in 0..n {
+                    let col: Vec<i8> = (0..k).map(|p| b[p * n + j]).collect();
+                    let expect = dot_scalar(&a[i * k..(i + 1) * k], &col);
+                    assert_eq!(c[i * n + j], expect, "{}x{}x{} at {},{}", m, n, k, i, j);
+                }
+            }
+        }
+
+        // extreme values
+        let (m, n, k) = (5, 9, 37);
+        let a = vec![i8::MIN; m * k];
+        let b = vec![i8::MIN; k * n];
+        let mut c = vec![0; m * n];
+        gemm_i8(m, n, k, &a, &b, &mut c);
+        assert!(c.iter().all(|&x| x == 128 * 128 * k as i32));
+    }
+}
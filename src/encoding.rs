@@ -0,0 +1,553 @@
+use crate::vectori128::{lookup16, select, Vec16bc, Vec16c};
+use alloc::{string::String, vec::Vec};
+use core::arch::x86_64::*;
+use core::fmt;
+
+/// Error returned by the decoders
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input length is not possible for this encoding
+    InvalidLength { length: usize },
+    /// The byte at position is not allowed there
+    InvalidByte { position: usize, byte: u8 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DecodeError::InvalidLength { length } => write!(f, "invalid input length {}", length),
+            DecodeError::InvalidByte { position, byte } => {
+                write!(f, "invalid byte {:#04x} at position {}", byte, position)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Alphabet and padding of a base64 encoding
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Base64 {
+    url_safe: bool,
+    pad: bool,
+}
+
+impl Base64 {
+    /// RFC 4648 alphabet with + and /, padded with =
+    pub const STANDARD: Base64 = Base64 {
+        url_safe: false,
+        pad: true,
+    };
+    /// RFC 4648 alphabet with + and /, without padding
+    pub const STANDARD_NO_PAD: Base64 = Base64 {
+        url_safe: false,
+        pad: false,
+    };
+    /// RFC 4648 URL and filename safe alphabet with - and _, padded with =
+    pub const URL_SAFE: Base64 = Base64 {
+        url_safe: true,
+        pad: true,
+    };
+    /// RFC 4648 URL and filename safe alphabet with - and _, without padding
+    pub const URL_SAFE_NO_PAD: Base64 = Base64 {
+        url_safe: true,
+        pad: false,
+    };
+
+    /// Characters for the values 62 and 63
+    #[inline]
+    fn extra(self) -> (u8, u8) {
+        if self.url_safe {
+            (b'-', b'_')
+        } else {
+            (b'+', b'/')
+        }
+    }
+}
+
+/// Encode bytes as lowercase hexadecimal, two characters per byte
+pub fn hex_encode(input: &[u8]) -> String {
+    let mut out = Vec::with_capacity(2 * input.len());
+    let digits = Vec16c::set_values(core::array::from_fn(|i| HEX_DIGITS[i] as i8));
+    let mut v = Vec16c::new();
+    let chunks = input.chunks_exact(16);
+    let rest = chunks.remainder();
+    for chunk in chunks {
+        let mut x: [u8; 32] = [0; 32];
+        // SAFETY: each chunk is 16 bytes long and x has room for 32
+        unsafe {
+            v.load(chunk.as_ptr() as *const i8);
+            let lo = lookup16(v, digits);
+            let hi = lookup16(
+                Vec16c {
+                    xmm: _mm_srli_epi16(v.xmm, 4),
+                },
+                digits,
+            );
+            // interleave so that the high digit comes first
+            let p = &mut x as *mut u8 as *mut __m128i;
+            _mm_storeu_si128(p, _mm_unpacklo_epi8(hi.xmm, lo.xmm));
+            _mm_storeu_si128(p.add(1), _mm_unpackhi_epi8(hi.xmm, lo.xmm));
+        }
+        out.extend_from_slice(&x);
+    }
+    for &b in rest {
+        out.push(HEX_DIGITS[(b >> 4) as usize]);
+        out.push(HEX_DIGITS[(b & 0x0F) as usize]);
+    }
+    // SAFETY: all characters are ASCII
+    unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decode hexadecimal text, accepting both lowercase and uppercase digits
+pub fn hex_decode(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    if !input.len().is_multiple_of(2) {
+        return Err(DecodeError::InvalidLength {
+            length: input.len(),
+        });
+    }
+    let mut out = Vec::with_capacity(input.len() / 2);
+    let mut a = Vec16c::new();
+    let mut b = Vec16c::new();
+    let chunks = input.chunks_exact(32);
+    let rest = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        // SAFETY: each chunk is 32 bytes long
+        unsafe {
+            a.load(chunk.as_ptr() as *const i8);
+            b.load(chunk.as_ptr().add(16) as *const i8);
+        }
+        let (va, bad_a) = hex_values(a);
+        let (vb, bad_b) = hex_values(b);
+        let bad = bad_a as u32 | (bad_b as u32) << 16;
+        if bad != 0 {
+            let position = 32 * i + bad.trailing_zeros() as usize;
+            return Err(invalid_byte(input, position));
+        }
+        let mut x: [u8; 16] = [0; 16];
+        // SAFETY: x has room for 16 bytes
+        unsafe {
+            // each 16-bit element holds the high digit in its low byte and the low digit above
+            let merge = |v: Vec16c| {
+                let hi = _mm_and_si128(v.xmm, _mm_set1_epi16(0x00FF));
+                let lo = _mm_srli_epi16(v.xmm, 8);
+                _mm_or_si128(_mm_slli_epi16(hi, 4), lo)
+            };
+            let packed = _mm_packus_epi16(merge(va), merge(vb));
+            _mm_storeu_si128(&mut x as *mut u8 as *mut __m128i, packed);
+        }
+        out.extend_from_slice(&x);
+    }
+    let start = input.len() - rest.len();
+    for (i, pair) in rest.chunks_exact(2).enumerate() {
+        let hi = hex_value(pair[0]).ok_or_else(|| invalid_byte(input, start + 2 * i))?;
+        let lo = hex_value(pair[1]).ok_or_else(|| invalid_byte(input, start + 2 * i + 1))?;
+        out.push(hi << 4 | lo);
+    }
+    Ok(out)
+}
+
+/// Encode bytes as base64
+pub fn base64_encode(input: &[u8], config: Base64) -> String {
+    let mut out = Vec::with_capacity(input.len().div_ceil(3) * 4);
+    #[cfg(target_feature = "ssse3")]
+    let i = {
+        let mut i = 0;
+        // 12 bytes give 16 characters. Each load reads 16 bytes, so stop 4 bytes early
+        let mut v = Vec16c::new();
+        while i + 16 <= input.len() {
+            let mut x: [u8; 16] = [0; 16];
+            // SAFETY: i + 16 <= input.len() and x has room for 16 bytes
+            unsafe {
+                v.load(input.as_ptr().add(i) as *const i8);
+                base64_encode_block(v, config).store(&mut x as *mut u8 as *mut i8);
+            }
+            out.extend_from_slice(&x);
+            i += 12;
+        }
+        i
+    };
+    #[cfg(not(target_feature = "ssse3"))]
+    let i = 0;
+    let alphabet = base64_alphabet(config);
+    for chunk in input[i..].chunks(3) {
+        let n = chunk.len();
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let sextets = [
+            b[0] >> 2,
+            (b[0] & 3) << 4 | b[1] >> 4,
+            (b[1] & 15) << 2 | b[2] >> 6,
+            b[2] & 63,
+        ];
+        out.extend(sextets[..n + 1].iter().map(|&s| alphabet[s as usize]));
+        if config.pad {
+            out.extend(core::iter::repeat_n(b'=', 3 - n));
+        }
+    }
+    // SAFETY: all characters are ASCII
+    unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decode base64 text. With padding the length must be a multiple of 4, and = may only
+/// appear as the last one or two characters. Without padding = is not allowed.
+/// Unused bits in the last character are ignored
+pub fn base64_decode(input: &[u8], config: Base64) -> Result<Vec<u8>, DecodeError> {
+    let mut body = input;
+    if config.pad {
+        if !input.len().is_multiple_of(4) {
+            return Err(DecodeError::InvalidLength {
+                length: input.len(),
+            });
+        }
+        for _ in 0..2 {
+            if let Some((b'=', head)) = body.split_last() {
+                body = head;
+            }
+        }
+    }
+
+    let mut out = Vec::with_capacity(body.len() / 4 * 3 + 2);
+    let mut v = Vec16c::new();
+    let chunks = body.chunks_exact(16);
+    let rest = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        // SAFETY: each chunk is 16 bytes long
+        unsafe { v.load(chunk.as_ptr() as *const i8) };
+        let (values, bad) = base64_values(v, config);
+        if bad != 0 {
+            let position = 16 * i + bad.trailing_zeros() as usize;
+            return Err(invalid_byte(input, position));
+        }
+        let mut x: [u32; 4] = [0; 4];
+        // SAFETY: x has room for 16 bytes
+        unsafe {
+            // join sextets into 12-bit pairs, then into 24 bits per 32-bit element
+            let even = _mm_and_si128(values.xmm, _mm_set1_epi16(0x00FF));
+            let odd = _mm_srli_epi16(values.xmm, 8);
+            let pairs = _mm_or_si128(_mm_slli_epi16(even, 6), odd);
+            let triples = _mm_madd_epi16(pairs, _mm_set1_epi32(0x0001_1000));
+            _mm_storeu_si128(&mut x as *mut u32 as *mut __m128i, triples);
+        }
+        for t in x {
+            out.extend_from_slice(&t.to_be_bytes()[1..]);
+        }
+    }
+
+    let start = body.len() - rest.len();
+    let alphabet = base64_alphabet(config);
+    for (i, quad) in rest.chunks(4).enumerate() {
+        let mut t = 0u32;
+        for (j, &c) in quad.iter().enumerate() {
+            let s = alphabet
+                .iter()
+                .position(|&a| a == c)
+                .ok_or_else(|| invalid_byte(input, start + 4 * i + j))?;
+            t |= (s as u32) << (18 - 6 * j);
+        }
+        if quad.len() == 1 {
+            return Err(DecodeError::InvalidLength {
+                length: input.len(),
+            });
+        }
+        out.extend_from_slice(&t.to_be_bytes()[1..quad.len()]);
+    }
+    Ok(out)
+}
+
+const HEX_DIGITS: [u8; 16] = *b"0123456789abcdef";
+
+fn invalid_byte(input: &[u8], position: usize) -> DecodeError {
+    DecodeError::InvalidByte {
+        position,
+        byte: input[position],
+    }
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn base64_alphabet(config: Base64) -> [u8; 64] {
+    let (c62, c63) = config.extra();
+    core::array::from_fn(|i| match i as u8 {
+        i @ 0..=25 => b'A' + i,
+        i @ 26..=51 => b'a' + i - 26,
+        i @ 52..=61 => b'0' + i - 52,
+        62 => c62,
+        _ => c63,
+    })
+}
+
+/// Elements of v within first..=last
+#[inline]
+fn in_range(v: Vec16c, first: u8, last: u8) -> __m128i {
+    unsafe {
+        // shift the range to the bottom of the signed range so that one signed compare suffices
+        let shifted = _mm_add_epi8(v.xmm, _mm_set1_epi8(0x80u8.wrapping_sub(first) as i8));
+        let limit = _mm_set1_epi8(0x80u8.wrapping_add(last - first + 1) as i8);
+        _mm_cmplt_epi8(shifted, limit)
+    }
+}
+
+/// Elements of v equal to c
+#[inline]
+fn equal(v: Vec16c, c: u8) -> __m128i {
+    unsafe { _mm_cmpeq_epi8(v.xmm, _mm_set1_epi8(c as i8)) }
+}
+
+/// Add offset to the elements of v selected by s, which are assumed not to overlap
+#[inline]
+fn add_where(r: Vec16c, s: __m128i, v: Vec16c, offset: i8) -> Vec16c {
+    select(Vec16bc::set_value(s), v + Vec16c::set_value(offset), r)
+}
+
+/// Values of 16 hex digits, and the bits of the elements that are not hex digits
+#[inline]
+fn hex_values(v: Vec16c) -> (Vec16c, u16) {
+    let digit = in_range(v, b'0', b'9');
+    let lower = in_range(v, b'a', b'f');
+    let upper = in_range(v, b'A', b'F');
+    let mut r = Vec16c::new();
+    r = add_where(r, digit, v, -(b'0' as i8));
+    r = add_where(r, lower, v, 10 - b'a' as i8);
+    r = add_where(r, upper, v, 10 - b'A' as i8);
+    let valid = unsafe { _mm_or_si128(_mm_or_si128(digit, lower), upper) };
+    (r, !(unsafe { _mm_movemask_epi8(valid) }) as u16)
+}
+
+/// Values of 16 base64 characters, and the bits of the elements that are not in the alphabet
+#[inline]
+fn base64_values(v: Vec16c, config: Base64) -> (Vec16c, u16) {
+    let (c62, c63) = config.extra();
+    let upper = in_range(v, b'A', b'Z');
+    let lower = in_range(v, b'a', b'z');
+    let digit = in_range(v, b'0', b'9');
+    let e62 = equal(v, c62);
+    let e63 = equal(v, c63);
+    let mut r = Vec16c::new();
+    r = add_where(r, upper, v, -(b'A' as i8));
+    r = add_where(r, lower, v, 26 - b'a' as i8);
+    r = add_where(r, digit, v, 52 - b'0' as i8);
+    r = add_where(r, e62, v, 62 - c62 as i8);
+    r = add_where(r, e63, v, 63 - c63 as i8);
+    let valid = unsafe {
+        _mm_or_si128(
+            _mm_or_si128(_mm_or_si128(upper, lower), digit),
+            _mm_or_si128(e62, e63),
+        )
+    };
+    (r, !(unsafe { _mm_movemask_epi8(valid) }) as u16)
+}
+
+/// Encode the first 12 bytes of v into 16 base64 characters
+#[cfg(target_feature = "ssse3")]
+#[inline]
+fn base64_encode_block(v: Vec16c, config: Base64) -> Vec16c {
+    unsafe {
+        // put the 3 bytes of each group into a 32-bit element as b1 b0 b2 b1
+        let x = _mm_shuffle_epi8(
+            v.xmm,
+            _mm_setr_epi8(1, 0, 2, 1, 4, 3, 5, 4, 7, 6, 8, 7, 10, 9, 11, 10),
+        );
+        // move the four sextets into separate bytes with multiplies
+        let t0 = _mm_and_si128(x, _mm_set1_epi32(0x0FC0_FC00));
+        let t1 = _mm_mulhi_epu16(t0, _mm_set1_epi32(0x0400_0040));
+        let t2 = _mm_and_si128(x, _mm_set1_epi32(0x003F_03F0));
+        let t3 = _mm_mullo_epi16(t2, _mm_set1_epi32(0x0100_0010));
+        let sextets = Vec16c {
+            xmm: _mm_or_si128(t1, t3),
+        };
+
+        // offset to add to each value: 0..=25 -> 13, 26..=51 -> 0, 52..=61 -> 1..=10,
+        // 62 -> 11, 63 -> 12, looked up in a table
+        let (c62, c63) = config.extra();
+        let reduced = _mm_subs_epu8(sextets.xmm, _mm_set1_epi8(51));
+        let less = _mm_cmpgt_epi8(_mm_set1_epi8(26), sextets.xmm);
+        let index = _mm_or_si128(reduced, _mm_and_si128(less, _mm_set1_epi8(13)));
+        let d = (b'0' as i8).wrapping_sub(52);
+        let offsets = Vec16c::set_values([
+            (b'a' - 26) as i8,
+            d,
+            d,
+            d,
+            d,
+            d,
+            d,
+            d,
+            d,
+            d,
+            d,
+            (c62 as i8).wrapping_sub(62),
+            (c63 as i8).wrapping_sub(63),
+            b'A' as i8,
+            0,
+            0,
+        ]);
+        lookup16(Vec16c { xmm: index }, offsets) + sextets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    // straightforward reference implementations
+
+    fn hex_encode_ref(input: &[u8]) -> String {
+        input.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn base64_encode_ref(input: &[u8], config: Base64) -> String {
+        let alphabet = base64_alphabet(config);
+        let mut bits = String::new();
+        for b in input {
+            bits += &format!("{:08b}", b);
+        }
+        while !bits.len().is_multiple_of(6) {
+            bits.push('0');
+        }
+        let mut out: String = bits
+            .as_bytes()
+            .chunks(6)
+            .map(|s| {
+                alphabet[usize::from_str_radix(core::str::from_utf8(s).unwrap(), 2).unwrap()]
+                    as char
+            })
+            .collect();
+        if config.pad {
+            while !out.len().is_multiple_of(4) {
+                out.push('=');
+            }
+        }
+        out
+    }
+
+    const CONFIGS: [Base64; 4] = [
+        Base64::STANDARD,
+        Base64::STANDARD_NO_PAD,
+        Base64::URL_SAFE,
+        Base64::URL_SAFE_NO_PAD,
+    ];
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(hex_encode(b"\x00\x7f\x80\xff"), "007f80ff");
+        assert_eq!(hex_decode(b"007F80fF"), Ok(vec![0x00, 0x7f, 0x80, 0xff]));
+        assert_eq!(base64_encode(b"foobar", Base64::STANDARD), "Zm9vYmFy");
+        assert_eq!(base64_encode(b"fooba", Base64::STANDARD), "Zm9vYmE=");
+        assert_eq!(base64_encode(b"foob", Base64::STANDARD), "Zm9vYg==");
+        assert_eq!(base64_encode(b"foob", Base64::URL_SAFE_NO_PAD), "Zm9vYg");
+        assert_eq!(base64_encode(&[0xfb, 0xff], Base64::STANDARD), "+/8=");
+        assert_eq!(base64_encode(&[0xfb, 0xff], Base64::URL_SAFE), "-_8=");
+        assert_eq!(
+            base64_decode(b"Zm9vYg==", Base64::STANDARD),
+            Ok(b"foob".to_vec())
+        );
+        assert_eq!(
+            base64_decode(b"Zm9vYg", Base64::STANDARD_NO_PAD),
+            Ok(b"foob".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = rand::thread_rng();
+
+        for len in 0..200 {
+            let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+
+            let hex = hex_encode(&data);
+            assert_eq!(hex, hex_encode_ref(&data));
+            assert_eq!(hex_decode(hex.as_bytes()), Ok(data.clone()));
+            assert_eq!(hex_decode(hex.to_uppercase().as_bytes()), Ok(data.clone()));
+
+            for config in CONFIGS {
+                let text = base64_encode(&data, config);
+                assert_eq!(text, base64_encode_ref(&data, config), "{:?}", config);
+                assert_eq!(base64_decode(text.as_bytes(), config), Ok(data.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let mut rng = rand::thread_rng();
+
+        assert_eq!(
+            hex_decode(b"abc"),
+            Err(DecodeError::InvalidLength { length: 3 })
+        );
+        assert_eq!(
+            base64_decode(b"Zm9vY", Base64::STANDARD_NO_PAD),
+            Err(DecodeError::InvalidLength { length: 5 })
+        );
+        assert_eq!(
+            base64_decode(b"Zm9vYg=", Base64::STANDARD),
+            Err(DecodeError::InvalidLength { length: 7 })
+        );
+        assert_eq!(
+            base64_decode(b"Zm9vYg==", Base64::STANDARD_NO_PAD),
+            Err(DecodeError::InvalidByte {
+                position: 6,
+                byte: b'='
+            })
+        );
+        assert_eq!(
+            base64_decode(b"Zm9v=mFy", Base64::STANDARD),
+            Err(DecodeError::InvalidByte {
+                position: 4,
+                byte: b'='
+            })
+        );
+        assert_eq!(
+            base64_decode(b"A===", Base64::STANDARD),
+            Err(DecodeError::InvalidByte {
+                position: 1,
+                byte: b'='
+            })
+        );
+
+        // one bad character at every position, in and after the vector blocks
+        for len in 1..100 {
+            let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let hex = hex_encode(&data).into_bytes();
+            for position in 0..hex.len() {
+                let mut bad = hex.clone();
+                bad[position] = b"g/ \xff"[rng.gen_range(0..4)];
+                assert_eq!(
+                    hex_decode(&bad),
+                    Err(DecodeError::InvalidByte {
+                        position,
+                        byte: bad[position]
+                    })
+                );
+            }
+            for config in CONFIGS {
+                let text = base64_encode(&data, config).into_bytes();
+                let body = text.iter().take_while(|&&c| c != b'=').count();
+                for position in 0..body {
+                    let mut bad = text.clone();
+                    bad[position] = if config.url_safe { b'+' } else { b'_' };
+                    assert_eq!(
+                        base64_decode(&bad, config),
+                        Err(DecodeError::InvalidByte {
+                            position,
+                            byte: bad[position]
+                        })
+                    );
+                }
+            }
+        }
+    }
+}
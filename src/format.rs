@@ -0,0 +1,239 @@
+use crate::vectori128::{Vec128b, Vec16bc, Vec16c};
+use core::fmt;
+use core::str::FromStr;
+
+// Vectors are written as a list of the elements in order, element 0 first: "[1, 2, -3, ...]".
+// Debug puts the type name in front. The number formats apply to each element, with the
+// formatting flags, so that {:02x} gives a two digit hex number per element. Boolean vectors
+// are written as "[T, F, ...]", or as a string of 16 ones and zeros with {:b}.
+// FromStr parses the Display and the Debug output
+
+/// Error returned when parsing a vector
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseVectorError {
+    /// The text is not a list in square brackets
+    Syntax,
+    /// The list does not have one entry per element
+    Length { found: usize },
+    /// The entry at index is not a valid element
+    Element { index: usize },
+}
+
+impl fmt::Display for ParseVectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseVectorError::Syntax => write!(f, "expected a list in square brackets"),
+            ParseVectorError::Length { found } => {
+                write!(f, "expected 16 elements, found {}", found)
+            }
+            ParseVectorError::Element { index } => write!(f, "invalid element at index {}", index),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseVectorError {}
+
+/// Write the elements as "[a, b, ...]", each with the formatting function f
+fn write_list<T>(
+    f: &mut fmt::Formatter<'_>,
+    elems: &[T],
+    fmt_elem: impl Fn(&T, &mut fmt::Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    f.write_str("[")?;
+    for (i, x) in elems.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        fmt_elem(x, f)?;
+    }
+    f.write_str("]")
+}
+
+/// Parse "[a, b, ...]" with 16 entries, optionally preceded by the type name
+fn parse_list<T: Copy + Default>(
+    s: &str,
+    name: &str,
+    parse_elem: impl Fn(&str) -> Option<T>,
+) -> Result<[T; 16], ParseVectorError> {
+    let s = s.trim();
+    let s = s.strip_prefix(name).unwrap_or(s);
+    let list = s
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or(ParseVectorError::Syntax)?;
+    let found = list.split(',').count();
+    if found != 16 {
+        return Err(ParseVectorError::Length { found });
+    }
+    let mut x = [T::default(); 16];
+    for (index, item) in list.split(',').enumerate() {
+        x[index] = parse_elem(item.trim()).ok_or(ParseVectorError::Element { index })?;
+    }
+    Ok(x)
+}
+
+macro_rules! impl_number_formats {
+    ($t:ty) => {
+        impl fmt::Debug for $t {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(stringify!($t))?;
+                write_list(f, &self.to_array(), fmt::Debug::fmt)
+            }
+        }
+
+        impl fmt::Display for $t {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_list(f, &self.to_array(), fmt::Display::fmt)
+            }
+        }
+
+        impl fmt::LowerHex for $t {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_list(f, &self.to_array(), fmt::LowerHex::fmt)
+            }
+        }
+
+        impl fmt::UpperHex for $t {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_list(f, &self.to_array(), fmt::UpperHex::fmt)
+            }
+        }
+
+        impl fmt::Binary for $t {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_list(f, &self.to_array(), fmt::Binary::fmt)
+            }
+        }
+    };
+}
+
+impl_number_formats!(Vec128b);
+impl_number_formats!(Vec16c);
+
+impl FromStr for Vec128b {
+    type Err = ParseVectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let x = parse_list(s, "Vec128b", |t| t.parse::<u8>().ok())?;
+        Ok(Vec128b::from_array(x))
+    }
+}
+
+impl FromStr for Vec16c {
+    type Err = ParseVectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let x = parse_list(s, "Vec16c", |t| t.parse::<i8>().ok())?;
+        Ok(Vec16c::set_values(x))
+    }
+}
+
+impl fmt::Debug for Vec16bc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Vec16bc")?;
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for Vec16bc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_list(f, &self.to_array(), |&b, f| {
+            f.write_str(if b { "T" } else { "F" })
+        })
+    }
+}
+
+/// Bit string with element 0 first
+impl fmt::Binary for Vec16bc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in self.to_array() {
+            f.write_str(if b { "1" } else { "0" })?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Vec16bc {
+    type Err = ParseVectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let x = parse_list(s, "Vec16bc", |t| match t {
+            "T" => Some(true),
+            "F" => Some(false),
+            _ => None,
+        })?;
+        Ok(Vec16bc::from_array(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vectori128::compare_lt;
+    use rand::Rng;
+
+    #[test]
+    fn test_format() {
+        let a = Vec16c::set_values([1, 2, -3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, -1, 127]);
+        let list = "[1, 2, -3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, -1, 127]";
+        assert_eq!(a.to_string(), list);
+        assert_eq!(format!("{:?}", a), format!("Vec16c{}", list));
+        assert_eq!(
+            format!("{:02x}", a),
+            "[01, 02, fd, 04, 05, 06, 07, 08, 09, 0a, 0b, 0c, 0d, 0e, ff, 7f]"
+        );
+        assert!(format!("{:#X}", a).starts_with("[0x1, 0x2, 0xFD, "));
+        assert!(format!("{:08b}", a).starts_with("[00000001, 00000010, 11111101, "));
+
+        let b = Vec128b::from(a);
+        assert!(b.to_string().starts_with("[1, 2, 253, 4, "));
+        assert!(format!("{:?}", b).starts_with("Vec128b[1, 2, 253, "));
+
+        let m = compare_lt(a, Vec16c::new());
+        let bools = "[F, F, T, F, F, F, F, F, F, F, F, F, F, F, T, F]";
+        assert_eq!(m.to_string(), bools);
+        assert_eq!(format!("{:?}", m), format!("Vec16bc{}", bools));
+        assert_eq!(format!("{:b}", m), "0010000000000010");
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            let x: [i8; 16] = rng.gen();
+            let a = Vec16c::set_values(x);
+            assert_eq!(a.to_string().parse::<Vec16c>(), Ok(a));
+            assert_eq!(format!("{:?}", a).parse::<Vec16c>(), Ok(a));
+            let b = Vec128b::from(a);
+            assert_eq!(Vec16c::from(b.to_string().parse::<Vec128b>().unwrap()), a);
+            let m = compare_lt(a, Vec16c::new());
+            let parsed: Vec16bc = m.to_string().parse().unwrap();
+            assert_eq!(parsed.to_string(), m.to_string());
+            let parsed: Vec16bc = format!("{:?}", m).parse().unwrap();
+            assert_eq!(parsed.to_string(), m.to_string());
+        }
+
+        let spaced = " [ 0,1 ,2,3,4,5,6,7,8,9,10,11,12,13,14,15 ] ";
+        assert_eq!(
+            spaced.parse::<Vec16c>(),
+            Ok(Vec16c::set_values(core::array::from_fn(|i| i as i8)))
+        );
+        assert_eq!("1, 2".parse::<Vec16c>(), Err(ParseVectorError::Syntax));
+        assert_eq!(
+            "[1, 2]".parse::<Vec16c>(),
+            Err(ParseVectorError::Length { found: 2 })
+        );
+        let bad = "[0, 0, 0, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]";
+        assert_eq!(
+            bad.parse::<Vec16c>(),
+            Err(ParseVectorError::Element { index: 3 })
+        );
+        assert!(bad.parse::<Vec128b>().is_ok());
+        assert_eq!(
+            "Vec16c[T, F]".parse::<Vec16bc>().unwrap_err(),
+            ParseVectorError::Syntax
+        );
+    }
+}
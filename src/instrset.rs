@@ -0,0 +1,226 @@
+use core::arch::x86_64::*;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+/// Instruction set levels, in the order of VCL's instrset_detect(). Each level includes the
+/// ones below it, and the levels from Avx up also require the operating system to save the
+/// wider registers
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InstructionSet {
+    Sse2 = 2,
+    Sse3 = 3,
+    Ssse3 = 4,
+    Sse41 = 5,
+    Sse42 = 6,
+    Avx = 7,
+    Avx2 = 8,
+    Avx512F = 9,
+    /// AVX-512 F, BW, DQ and VL
+    Avx512BwDqVl = 10,
+}
+
+impl InstructionSet {
+    /// The level as the number returned by VCL's instrset_detect()
+    pub fn level(self) -> i32 {
+        self as i32
+    }
+
+    /// The instruction set with the given VCL level number
+    pub fn from_level(level: i32) -> Option<Self> {
+        use InstructionSet::*;
+        Some(match level {
+            2 => Sse2,
+            3 => Sse3,
+            4 => Ssse3,
+            5 => Sse41,
+            6 => Sse42,
+            7 => Avx,
+            8 => Avx2,
+            9 => Avx512F,
+            10 => Avx512BwDqVl,
+            _ => return None,
+        })
+    }
+}
+
+/// The level from detect(), with VBMI added as bit 8 for has_avx512vbmi. -1 until the first call
+static LEVEL: AtomicI32 = AtomicI32::new(-1);
+const VBMI: i32 = 1 << 8;
+
+fn cached() -> i32 {
+    let mut level = LEVEL.load(Ordering::Relaxed);
+    if level < 0 {
+        let set = detect();
+        level = set.level();
+        if set >= InstructionSet::Avx512BwDqVl && cpuid(7, 0).ecx & (1 << 1) != 0 {
+            level |= VBMI;
+        }
+        LEVEL.store(level, Ordering::Relaxed);
+    }
+    level
+}
+
+/// Detect the highest instruction set level supported by both the CPU and the operating
+/// system. The result is cached after the first call
+pub fn instrset_detect() -> InstructionSet {
+    InstructionSet::from_level(cached() & 0xFF).unwrap()
+}
+
+/// The SSE4.2 string instructions and crc32
+pub fn has_sse42() -> bool {
+    instrset_detect() >= InstructionSet::Sse42
+}
+
+/// Fused multiply-add with three operands
+pub fn has_fma3() -> bool {
+    ymm_enabled() && cpuid(1, 0).ecx & (1 << 12) != 0
+}
+
+/// Conversions between half and single precision
+pub fn has_f16c() -> bool {
+    ymm_enabled() && cpuid(1, 0).ecx & (1 << 29) != 0
+}
+
+/// The popcnt instruction
+pub fn has_popcnt() -> bool {
+    cpuid(1, 0).ecx & (1 << 23) != 0
+}
+
+/// Bit manipulation instructions set 2 (pdep, pext, ...)
+pub fn has_bmi2() -> bool {
+    max_leaf() >= 7 && cpuid(7, 0).ebx & (1 << 8) != 0
+}
+
+/// VEX encoded AVX-VNNI dot product instructions
+pub fn has_avx_vnni() -> bool {
+    ymm_enabled() && max_leaf() >= 7 && cpuid(7, 1).eax & (1 << 4) != 0
+}
+
+/// AVX-512 byte permutes (vpermb, vpermi2b). Cached with the instruction set level, so that
+/// it is cheap enough to check on every call of a dispatched function
+pub fn has_avx512vbmi() -> bool {
+    cached() & VBMI != 0
+}
+
+/// Carry-less multiplication on 256 and 512 bit vectors
+pub fn has_vpclmulqdq() -> bool {
+    ymm_enabled() && max_leaf() >= 7 && cpuid(7, 0).ecx & (1 << 10) != 0
+}
+
+fn detect() -> InstructionSet {
+    use InstructionSet::*;
+
+    let ecx1 = cpuid(1, 0).ecx;
+    if ecx1 & (1 << 0) == 0 {
+        return Sse2;
+    }
+    if ecx1 & (1 << 9) == 0 {
+        return Sse3;
+    }
+    if ecx1 & (1 << 19) == 0 {
+        return Ssse3;
+    }
+    if ecx1 & (1 << 20) == 0 {
+        return Sse41;
+    }
+    if !ymm_enabled() || ecx1 & (1 << 28) == 0 {
+        return Sse42;
+    }
+    let ebx7 = if max_leaf() >= 7 { cpuid(7, 0).ebx } else { 0 };
+    if ebx7 & (1 << 5) == 0 {
+        return Avx;
+    }
+    // xgetbv bits 5 to 7 are the opmask and zmm state
+    if xcr0() & 0xE0 != 0xE0 || ebx7 & (1 << 16) == 0 {
+        return Avx2;
+    }
+    let bw_dq_vl = (1 << 17) | (1 << 30) | (1 << 31);
+    if ebx7 & bw_dq_vl != bw_dq_vl {
+        return Avx512F;
+    }
+    Avx512BwDqVl
+}
+
+fn cpuid(leaf: u32, sub_leaf: u32) -> CpuidResult {
+    __cpuid_count(leaf, sub_leaf)
+}
+
+fn max_leaf() -> u32 {
+    cpuid(0, 0).eax
+}
+
+/// Contents of the XCR0 register, or 0 if the operating system does not use xsave
+fn xcr0() -> u64 {
+    #[target_feature(enable = "xsave")]
+    unsafe fn xgetbv0() -> u64 {
+        _xgetbv(0)
+    }
+
+    if cpuid(1, 0).ecx & (1 << 27) == 0 {
+        return 0;
+    }
+    // SAFETY: the osxsave bit says that xgetbv can be used
+    unsafe { xgetbv0() }
+}
+
+/// The operating system saves the xmm and ymm registers
+fn ymm_enabled() -> bool {
+    xcr0() & 6 == 6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instrset_detect() {
+        use InstructionSet::*;
+
+        let level = instrset_detect();
+        assert_eq!(instrset_detect(), level);
+        let expected = [
+            (Sse3, is_x86_feature_detected!("sse3")),
+            (Ssse3, is_x86_feature_detected!("ssse3")),
+            (Sse41, is_x86_feature_detected!("sse4.1")),
+            (Sse42, is_x86_feature_detected!("sse4.2")),
+            (Avx, is_x86_feature_detected!("avx")),
+            (Avx2, is_x86_feature_detected!("avx2")),
+            (Avx512F, is_x86_feature_detected!("avx512f")),
+            (
+                Avx512BwDqVl,
+                is_x86_feature_detected!("avx512bw")
+                    && is_x86_feature_detected!("avx512dq")
+                    && is_x86_feature_detected!("avx512vl"),
+            ),
+        ];
+        let mut highest = Sse2;
+        for (set, detected) in expected {
+            if !detected {
+                break;
+            }
+            highest = set;
+        }
+        assert_eq!(level, highest);
+        assert!(Sse2 < Avx && Avx < Avx512BwDqVl);
+        assert_eq!(Avx2.level(), 8);
+        for level in 2..=10 {
+            assert_eq!(InstructionSet::from_level(level).unwrap().level(), level);
+        }
+        assert_eq!(InstructionSet::from_level(11), None);
+    }
+
+    #[test]
+    fn test_features() {
+        assert_eq!(has_sse42(), is_x86_feature_detected!("sse4.2"));
+        assert_eq!(has_fma3(), is_x86_feature_detected!("fma"));
+        assert_eq!(has_f16c(), is_x86_feature_detected!("f16c"));
+        assert_eq!(has_popcnt(), is_x86_feature_detected!("popcnt"));
+        assert_eq!(has_bmi2(), is_x86_feature_detected!("bmi2"));
+        assert_eq!(has_avx_vnni(), is_x86_feature_detected!("avxvnni"));
+        assert_eq!(
+            has_avx512vbmi(),
+            instrset_detect() == InstructionSet::Avx512BwDqVl
+                && is_x86_feature_detected!("avx512vbmi")
+        );
+        assert_eq!(has_vpclmulqdq(), is_x86_feature_detected!("vpclmulqdq"));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::vectori128::{compare_lt, Vec128b, Vec16bc, Vec16c};
+
+// Safe access to the elements of a vector as an array. Vec128b is treated as 16 bytes and the
+// boolean vectors as 16 bools. Index is not implemented because the elements are in a register
+// and there is nothing to return a reference to
+
+fn bytes_to_array(a: Vec128b) -> [u8; 16] {
+    let mut x = [0u8; 16];
+    // SAFETY: x has room for 16 bytes
+    unsafe { a.store(x.as_mut_ptr()) };
+    x
+}
+
+fn bytes_from_array(x: [u8; 16]) -> Vec128b {
+    Vec16c::set_values(x.map(|b| b as i8)).into()
+}
+
+fn elems_to_array(a: Vec16c) -> [i8; 16] {
+    let mut x = [0i8; 16];
+    // SAFETY: x has room for 16 elements
+    unsafe { a.store(x.as_mut_ptr()) };
+    x
+}
+
+fn bools_to_array(a: Vec16bc) -> [bool; 16] {
+    elems_to_array(a.into()).map(|x| x < 0)
+}
+
+fn bools_from_array(x: [bool; 16]) -> Vec16bc {
+    compare_lt(Vec16c::set_values(x.map(|b| -(b as i8))), Vec16c::new())
+}
+
+macro_rules! impl_lanes {
+    ($t:ty, $elem:ty, $to_array:expr, $from_array:expr) => {
+        impl $t {
+            /// The elements as an array, element 0 first
+            #[inline]
+            pub fn to_array(self) -> [$elem; 16] {
+                $to_array(self)
+            }
+
+            /// Make a vector from an array, element 0 first
+            #[inline]
+            pub fn from_array(x: [$elem; 16]) -> Self {
+                $from_array(x)
+            }
+
+            /// Make a vector with element i equal to f(i)
+            #[inline]
+            pub fn from_fn(f: impl FnMut(usize) -> $elem) -> Self {
+                $from_array(core::array::from_fn(f))
+            }
+
+            /// Element number index, or None if index is out of range
+            #[inline]
+            pub fn get(self, index: usize) -> Option<$elem> {
+                self.to_array().get(index).copied()
+            }
+
+            /// Set element number index to value. Panics if index is out of range
+            #[inline]
+            pub fn set(&mut self, index: usize, value: $elem) {
+                let mut x = self.to_array();
+                x[index] = value;
+                *self = $from_array(x);
+            }
+        }
+
+        impl IntoIterator for $t {
+            type Item = $elem;
+            type IntoIter = core::array::IntoIter<$elem, 16>;
+
+            #[inline]
+            fn into_iter(self) -> Self::IntoIter {
+                self.to_array().into_iter()
+            }
+        }
+
+        /// Collect exactly 16 elements. Panics if the iterator gives fewer or more
+        impl FromIterator<$elem> for $t {
+            fn from_iter<I: IntoIterator<Item = $elem>>(iter: I) -> Self {
+                let mut iter = iter.into_iter();
+                let x = core::array::from_fn(|_| {
+                    iter.next()
+                        .expect("the iterator gives fewer than 16 elements")
+                });
+                assert!(
+                    iter.next().is_none(),
+                    "the iterator gives more than 16 elements"
+                );
+                $from_array(x)
+            }
+        }
+    };
+}
+
+impl_lanes!(Vec128b, u8, bytes_to_array, bytes_from_array);
+impl_lanes!(Vec16c, i8, elems_to_array, Vec16c::set_values);
+impl_lanes!(Vec16bc, bool, bools_to_array, bools_from_array);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vectori128::horizontal_count;
+    use rand::Rng;
+
+    #[test]
+    fn test_lanes() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            let x: [i8; 16] = rng.gen();
+            let mut a = Vec16c::from_array(x);
+            assert_eq!(a.to_array(), x);
+            assert_eq!(Vec16c::from_fn(|i| x[i]), a);
+            assert_eq!(a.into_iter().collect::<Vec<_>>(), x);
+            assert_eq!(x.iter().copied().collect::<Vec16c>(), a);
+            for (i, &e) in x.iter().enumerate() {
+                assert_eq!(a.get(i), Some(e));
+            }
+            assert_eq!(a.get(16), None);
+
+            let i = rng.gen_range(0..16);
+            let v = rng.gen();
+            a.set(i, v);
+            let mut y = x;
+            y[i] = v;
+            assert_eq!(a.to_array(), y);
+
+            let mut b = Vec128b::from(a);
+            assert_eq!(b.to_array(), y.map(|e| e as u8));
+            b.set(0, 200);
+            assert_eq!(b.get(0), Some(200));
+            assert_eq!(Vec128b::from_fn(|i| i as u8).into_iter().sum::<u8>(), 120);
+
+            let mut m = compare_lt(a, Vec16c::new());
+            let bools = y.map(|e| e < 0);
+            assert_eq!(m.to_array(), bools);
+            assert_eq!(Vec16bc::from_array(bools).to_array(), bools);
+            assert_eq!(bools.into_iter().collect::<Vec16bc>().to_array(), bools);
+            m.set(i, true);
+            assert_eq!(m.get(i), Some(true));
+            assert_eq!(m.get(16), None);
+            let count = m.into_iter().filter(|&b| b).count() as u32;
+            assert_eq!(count, horizontal_count(m));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_out_of_range() {
+        Vec16c::new().set(16, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_collect_too_few() {
+        let _: Vec16c = (0..15).collect();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_collect_too_many() {
+        let _: Vec16bc = [true; 17].into_iter().collect();
+    }
+}
@@ -1,2 +1,64 @@
-#[cfg(target_feature = "sse")]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
 pub mod vectori128;
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon", not(vec_lib_scalar)))]
+#[path = "vectori128_neon.rs"]
+pub mod vectori128;
+
+#[cfg(not(any(
+    all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)),
+    all(target_arch = "aarch64", target_feature = "neon", not(vec_lib_scalar))
+)))]
+#[path = "vectori128_scalar.rs"]
+pub mod vectori128;
+
+pub mod simd;
+
+pub mod format;
+
+mod lanes;
+
+// dot, reduce, search, strings, utf8, ascii, encoding and decimal use SSE intrinsics directly.
+// They are only built with the SSE backend, not with NEON or the scalar backend
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
+pub mod dot;
+
+pub mod slice;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
+pub mod reduce;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
+pub mod search;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
+pub mod strings;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
+pub mod utf8;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
+pub mod ascii;
+
+#[cfg(all(
+    target_arch = "x86_64",
+    target_feature = "sse2",
+    feature = "alloc",
+    not(vec_lib_scalar)
+))]
+pub mod encoding;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
+pub mod decimal;
+
+#[cfg(target_arch = "x86_64")]
+pub mod instrset;
+
+#[cfg(target_arch = "x86_64")]
+pub mod dispatch;
@@ -0,0 +1,520 @@
+use crate::vectori128::{horizontal_find_first, horizontal_max, horizontal_min, max, min};
+use crate::vectori128::{to_bits, Vec16bc, Vec16c};
+use core::arch::x86_64::*;
+use core::mem::size_of;
+
+/// Sum of all elements, without overflow for any slice length that fits in memory
+pub fn sum_i8(a: &[i8]) -> i64 {
+    unsafe {
+        // flipping the sign bit gives x + 128 as unsigned, which psadbw sums into 64-bit halves.
+        // Four independent accumulators hide the latency of the additions
+        let signbit = _mm_set1_epi8(i8::MIN);
+        let mut acc = [_mm_setzero_si128(); 4];
+        let chunks = a.chunks_exact(64);
+        let rest = chunks.remainder();
+        for chunk in chunks {
+            for (j, acc) in acc.iter_mut().enumerate() {
+                let x = _mm_loadu_si128(chunk.as_ptr().add(16 * j) as *const __m128i);
+                let s = _mm_sad_epu8(_mm_xor_si128(x, signbit), _mm_setzero_si128());
+                *acc = _mm_add_epi64(*acc, s);
+            }
+        }
+        let sum = _mm_add_epi64(_mm_add_epi64(acc[0], acc[1]), _mm_add_epi64(acc[2], acc[3]));
+        let sum = _mm_add_epi64(sum, _mm_unpackhi_epi64(sum, sum));
+        let vsum = _mm_cvtsi128_si64(sum) - 128 * (a.len() - rest.len()) as i64;
+        vsum + rest.iter().map(|&x| x as i64).sum::<i64>()
+    }
+}
+
+/// Smallest element, or None if the slice is empty
+pub fn min_i8(a: &[i8]) -> Option<i8> {
+    if a.is_empty() {
+        return None;
+    }
+    let mut acc = [Vec16c::set_value(i8::MAX); 4];
+    reduce_i8(a, i8::MAX, &mut acc, min);
+    Some(horizontal_min(min(
+        min(acc[0], acc[1]),
+        min(acc[2], acc[3]),
+    )))
+}
+
+/// Largest element, or None if the slice is empty
+pub fn max_i8(a: &[i8]) -> Option<i8> {
+    if a.is_empty() {
+        return None;
+    }
+    let mut acc = [Vec16c::set_value(i8::MIN); 4];
+    reduce_i8(a, i8::MIN, &mut acc, max);
+    Some(horizontal_max(max(
+        max(acc[0], acc[1]),
+        max(acc[2], acc[3]),
+    )))
+}
+
+/// Index of the first smallest element, or None if the slice is empty
+pub fn argmin_i8(a: &[i8]) -> Option<usize> {
+    min_i8(a).and_then(|m| position_i8(a, m))
+}
+
+/// Index of the first largest element, or None if the slice is empty
+pub fn argmax_i8(a: &[i8]) -> Option<usize> {
+    max_i8(a).and_then(|m| position_i8(a, m))
+}
+
+/// Number of elements equal to value
+pub fn count_eq(a: &[u8], value: u8) -> usize {
+    unsafe {
+        // each match subtracts -1 from a byte counter. The counters are widened with psadbw
+        // before they can overflow, after at most 255 rounds
+        let needle = _mm_set1_epi8(value as i8);
+        let mut total = _mm_setzero_si128();
+        let blocks = a.chunks(64 * 255);
+        for block in blocks {
+            let mut acc = [_mm_setzero_si128(); 4];
+            let chunks = block.chunks_exact(64);
+            for chunk in chunks {
+                for (j, acc) in acc.iter_mut().enumerate() {
+                    let x = _mm_loadu_si128(chunk.as_ptr().add(16 * j) as *const __m128i);
+                    *acc = _mm_sub_epi8(*acc, _mm_cmpeq_epi8(x, needle));
+                }
+            }
+            for acc in acc.iter() {
+                total = _mm_add_epi64(total, _mm_sad_epu8(*acc, _mm_setzero_si128()));
+            }
+        }
+        let total = _mm_add_epi64(total, _mm_unpackhi_epi64(total, total));
+        let rest = &a[a.len() - a.len() % 64..];
+        _mm_cvtsi128_si64(total) as usize + rest.iter().filter(|&&x| x == value).count()
+    }
+}
+
+/// Sum of all elements, without overflow for any slice length that fits in memory
+pub fn sum_i16(a: &[i16]) -> i64 {
+    unsafe {
+        // pmaddwd by 1 adds neighbouring pairs into 32-bit lanes. Each round adds less than 2^16
+        // to a lane, so the lanes are widened into the 64-bit total every 2^14 rounds
+        let ones = _mm_set1_epi16(1);
+        let mut total = _mm_setzero_si128();
+        let rest = &a[a.len() - a.len() % 32..];
+        for block in a[..a.len() - rest.len()].chunks(32 << 14) {
+            let mut acc = [_mm_setzero_si128(); 4];
+            for chunk in block.chunks_exact(32) {
+                for (j, acc) in acc.iter_mut().enumerate() {
+                    let x = _mm_loadu_si128(chunk.as_ptr().add(8 * j) as *const __m128i);
+                    *acc = _mm_add_epi32(*acc, _mm_madd_epi16(x, ones));
+                }
+            }
+            for acc in acc.iter() {
+                total = add_widened_i32(total, *acc);
+            }
+        }
+        horizontal_add_i64(total) + rest.iter().map(|&x| x as i64).sum::<i64>()
+    }
+}
+
+/// Sum of all elements, without overflow for slices of up to 2^32 elements
+pub fn sum_i32(a: &[i32]) -> i64 {
+    unsafe {
+        let mut acc = [_mm_setzero_si128(); 4];
+        let chunks = a.chunks_exact(16);
+        let rest = chunks.remainder();
+        for chunk in chunks {
+            for (j, acc) in acc.iter_mut().enumerate() {
+                let x = _mm_loadu_si128(chunk.as_ptr().add(4 * j) as *const __m128i);
+                *acc = add_widened_i32(*acc, x);
+            }
+        }
+        let total = _mm_add_epi64(_mm_add_epi64(acc[0], acc[1]), _mm_add_epi64(acc[2], acc[3]));
+        horizontal_add_i64(total) + rest.iter().map(|&x| x as i64).sum::<i64>()
+    }
+}
+
+/// Sum of all elements. The elements are added in four interleaved vector sums, not in order,
+/// so the rounding can differ from a sequential loop. A NaN element makes the sum NaN
+pub fn sum_f32(a: &[f32]) -> f32 {
+    unsafe {
+        let mut acc = [_mm_setzero_ps(); 4];
+        let chunks = a.chunks_exact(16);
+        let rest = chunks.remainder();
+        for chunk in chunks {
+            for (j, acc) in acc.iter_mut().enumerate() {
+                *acc = _mm_add_ps(*acc, _mm_loadu_ps(chunk.as_ptr().add(4 * j)));
+            }
+        }
+        let sum = _mm_add_ps(_mm_add_ps(acc[0], acc[1]), _mm_add_ps(acc[2], acc[3]));
+        let mut x: [f32; 4] = [0.0; 4];
+        _mm_storeu_ps(x.as_mut_ptr(), sum);
+        (x[0] + x[1]) + (x[2] + x[3]) + rest.iter().sum::<f32>()
+    }
+}
+
+/// Smallest element, or None if the slice is empty
+pub fn min_i16(a: &[i16]) -> Option<i16> {
+    let lanes: [i16; 8] = reduce(a, i16::MAX, |acc, x| unsafe { _mm_min_epi16(acc, x) })?;
+    lanes.into_iter().min()
+}
+
+/// Largest element, or None if the slice is empty
+pub fn max_i16(a: &[i16]) -> Option<i16> {
+    let lanes: [i16; 8] = reduce(a, i16::MIN, |acc, x| unsafe { _mm_max_epi16(acc, x) })?;
+    lanes.into_iter().max()
+}
+
+/// Index of the first smallest element, or None if the slice is empty
+pub fn argmin_i16(a: &[i16]) -> Option<usize> {
+    let m = min_i16(a)?;
+    position(a, m, |x| unsafe { _mm_cmpeq_epi16(x, _mm_set1_epi16(m)) })
+}
+
+/// Index of the first largest element, or None if the slice is empty
+pub fn argmax_i16(a: &[i16]) -> Option<usize> {
+    let m = max_i16(a)?;
+    position(a, m, |x| unsafe { _mm_cmpeq_epi16(x, _mm_set1_epi16(m)) })
+}
+
+/// Smallest element, or None if the slice is empty
+pub fn min_i32(a: &[i32]) -> Option<i32> {
+    let lanes: [i32; 4] = reduce(a, i32::MAX, |acc, x| unsafe { min_epi32(acc, x) })?;
+    lanes.into_iter().min()
+}
+
+/// Largest element, or None if the slice is empty
+pub fn max_i32(a: &[i32]) -> Option<i32> {
+    let lanes: [i32; 4] = reduce(a, i32::MIN, |acc, x| unsafe { max_epi32(acc, x) })?;
+    lanes.into_iter().max()
+}
+
+/// Index of the first smallest element, or None if the slice is empty
+pub fn argmin_i32(a: &[i32]) -> Option<usize> {
+    let m = min_i32(a)?;
+    position(a, m, |x| unsafe { _mm_cmpeq_epi32(x, _mm_set1_epi32(m)) })
+}
+
+/// Index of the first largest element, or None if the slice is empty
+pub fn argmax_i32(a: &[i32]) -> Option<usize> {
+    let m = max_i32(a)?;
+    position(a, m, |x| unsafe { _mm_cmpeq_epi32(x, _mm_set1_epi32(m)) })
+}
+
+// The f32 reductions skip NaN elements, like f32::min and f32::max. They return None if the
+// slice is empty or all NaN. 0.0 and -0.0 compare equal, so either may be returned when both
+// are present, and argmin/argmax give the index of the first of them
+
+/// Smallest element that is not NaN, or None if there is none
+pub fn min_f32(a: &[f32]) -> Option<f32> {
+    // minps returns its second operand if either is NaN, so a NaN element leaves acc unchanged
+    let lanes: [f32; 4] = reduce(a, f32::INFINITY, |acc, x| unsafe {
+        _mm_castps_si128(_mm_min_ps(_mm_castsi128_ps(x), _mm_castsi128_ps(acc)))
+    })?;
+    let m = lanes.into_iter().fold(f32::INFINITY, f32::min);
+    // infinity is also what is left when all elements are NaN
+    if m == f32::INFINITY && position_f32(a, m).is_none() {
+        return None;
+    }
+    Some(m)
+}
+
+/// Largest element that is not NaN, or None if there is none
+pub fn max_f32(a: &[f32]) -> Option<f32> {
+    let lanes: [f32; 4] = reduce(a, f32::NEG_INFINITY, |acc, x| unsafe {
+        _mm_castps_si128(_mm_max_ps(_mm_castsi128_ps(x), _mm_castsi128_ps(acc)))
+    })?;
+    let m = lanes.into_iter().fold(f32::NEG_INFINITY, f32::max);
+    if m == f32::NEG_INFINITY && position_f32(a, m).is_none() {
+        return None;
+    }
+    Some(m)
+}
+
+/// Index of the first smallest element that is not NaN, or None if there is none
+pub fn argmin_f32(a: &[f32]) -> Option<usize> {
+    position_f32(a, min_f32(a)?)
+}
+
+/// Index of the first largest element that is not NaN, or None if there is none
+pub fn argmax_f32(a: &[f32]) -> Option<usize> {
+    position_f32(a, max_f32(a)?)
+}
+
+/// Combine all elements of a into four accumulators with f, 64 elements at a time.
+/// The last partial vectors are padded with identity
+fn reduce_i8<F>(a: &[i8], identity: i8, acc: &mut [Vec16c; 4], f: F)
+where
+    F: Fn(Vec16c, Vec16c) -> Vec16c,
+{
+    let mut v = Vec16c::new();
+    let chunks = a.chunks_exact(64);
+    let rest = chunks.remainder();
+    for chunk in chunks {
+        for (j, acc) in acc.iter_mut().enumerate() {
+            // SAFETY: each chunk is 64 elements long
+            unsafe { v.load(chunk.as_ptr().add(16 * j)) };
+            *acc = f(*acc, v);
+        }
+    }
+    for part in rest.chunks(16) {
+        let mut x: [i8; 16] = [identity; 16];
+        x[..part.len()].copy_from_slice(part);
+        acc[0] = f(acc[0], Vec16c::set_values(x));
+    }
+}
+
+/// Index of the first element equal to value
+fn position_i8(a: &[i8], value: i8) -> Option<usize> {
+    let needle = Vec16c::set_value(value);
+    let mut v = Vec16c::new();
+    let chunks = a.chunks_exact(16);
+    let rest = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        // SAFETY: each chunk is 16 elements long
+        unsafe { v.load(chunk.as_ptr()) };
+        let eq = Vec16bc::set_value(unsafe { _mm_cmpeq_epi8(v.xmm, needle.xmm) });
+        if to_bits(eq) != 0 {
+            return horizontal_find_first(eq).map(|j| 16 * i + j);
+        }
+    }
+    rest.iter()
+        .position(|&x| x == value)
+        .map(|j| a.len() - rest.len() + j)
+}
+
+/// Combine all elements of a into four accumulators with f, four vectors of N elements at a
+/// time, and return the lanes of the combined accumulator, or None if a is empty.
+/// The last partial vectors are padded with identity
+fn reduce<T, const N: usize, F>(a: &[T], identity: T, f: F) -> Option<[T; N]>
+where
+    T: Copy,
+    F: Fn(__m128i, __m128i) -> __m128i,
+{
+    assert!(N * size_of::<T>() == 16);
+    if a.is_empty() {
+        return None;
+    }
+    unsafe {
+        let mut acc = [_mm_loadu_si128([identity; N].as_ptr() as *const __m128i); 4];
+        let chunks = a.chunks_exact(4 * N);
+        let rest = chunks.remainder();
+        for chunk in chunks {
+            for (j, acc) in acc.iter_mut().enumerate() {
+                let x = _mm_loadu_si128(chunk.as_ptr().add(N * j) as *const __m128i);
+                *acc = f(*acc, x);
+            }
+        }
+        for part in rest.chunks(N) {
+            let mut x = [identity; N];
+            x[..part.len()].copy_from_slice(part);
+            acc[0] = f(acc[0], _mm_loadu_si128(x.as_ptr() as *const __m128i));
+        }
+        let mut x = [identity; N];
+        let v = f(f(acc[0], acc[1]), f(acc[2], acc[3]));
+        _mm_storeu_si128(x.as_mut_ptr() as *mut __m128i, v);
+        Some(x)
+    }
+}
+
+/// Index of the first element equal to value. eq compares a vector of elements with value
+fn position<T, F>(a: &[T], value: T, eq: F) -> Option<usize>
+where
+    T: Copy + PartialEq,
+    F: Fn(__m128i) -> __m128i,
+{
+    let n = 16 / size_of::<T>();
+    let chunks = a.chunks_exact(n);
+    let rest = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        // SAFETY: each chunk is 16 bytes long
+        let bits =
+            unsafe { _mm_movemask_epi8(eq(_mm_loadu_si128(chunk.as_ptr() as *const __m128i))) };
+        if bits != 0 {
+            return Some(n * i + bits.trailing_zeros() as usize / size_of::<T>());
+        }
+    }
+    rest.iter()
+        .position(|&x| x == value)
+        .map(|j| a.len() - rest.len() + j)
+}
+
+/// Index of the first element equal to value, which is not NaN
+fn position_f32(a: &[f32], value: f32) -> Option<usize> {
+    position(a, value, |x| unsafe {
+        _mm_castps_si128(_mm_cmpeq_ps(_mm_castsi128_ps(x), _mm_set1_ps(value)))
+    })
+}
+
+/// Add the four 32-bit lanes of x, sign extended, to the two 64-bit lanes of acc
+#[inline]
+unsafe fn add_widened_i32(acc: __m128i, x: __m128i) -> __m128i {
+    let sign = _mm_srai_epi32(x, 31);
+    let lo = _mm_unpacklo_epi32(x, sign);
+    let hi = _mm_unpackhi_epi32(x, sign);
+    _mm_add_epi64(acc, _mm_add_epi64(lo, hi))
+}
+
+/// Sum of the two 64-bit lanes
+#[inline]
+unsafe fn horizontal_add_i64(x: __m128i) -> i64 {
+    _mm_cvtsi128_si64(_mm_add_epi64(x, _mm_unpackhi_epi64(x, x)))
+}
+
+/// Signed 32-bit minimum of each pair of lanes
+#[inline]
+unsafe fn min_epi32(a: __m128i, b: __m128i) -> __m128i {
+    #[cfg(target_feature = "sse4.1")]
+    {
+        _mm_min_epi32(a, b)
+    }
+    #[cfg(not(target_feature = "sse4.1"))]
+    {
+        let gt = _mm_cmpgt_epi32(a, b);
+        _mm_or_si128(_mm_and_si128(gt, b), _mm_andnot_si128(gt, a))
+    }
+}
+
+/// Signed 32-bit maximum of each pair of lanes
+#[inline]
+unsafe fn max_epi32(a: __m128i, b: __m128i) -> __m128i {
+    #[cfg(target_feature = "sse4.1")]
+    {
+        _mm_max_epi32(a, b)
+    }
+    #[cfg(not(target_feature = "sse4.1"))]
+    {
+        let gt = _mm_cmpgt_epi32(a, b);
+        _mm_or_si128(_mm_and_si128(gt, a), _mm_andnot_si128(gt, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_reductions() {
+        let mut rng = rand::thread_rng();
+
+        for len in (0..300).chain([1000, 4096, 20000]) {
+            let a: Vec<i8> = (0..len).map(|_| rng.gen()).collect();
+            // a narrow range makes repeated extremes and matches likely
+            let b: Vec<i8> = (0..len).map(|_| rng.gen_range(-3..3)).collect();
+            let u: Vec<u8> = (0..len).map(|_| rng.gen_range(0..4)).collect();
+            for a in [&a, &b] {
+                assert_eq!(sum_i8(a), a.iter().map(|&x| x as i64).sum::<i64>());
+                assert_eq!(min_i8(a), a.iter().copied().min());
+                assert_eq!(max_i8(a), a.iter().copied().max());
+                let m = a.iter().copied().min();
+                assert_eq!(argmin_i8(a), m.and_then(|m| a.iter().position(|&x| x == m)));
+                let m = a.iter().copied().max();
+                assert_eq!(argmax_i8(a), m.and_then(|m| a.iter().position(|&x| x == m)));
+            }
+            for value in 0..5 {
+                let expect = u.iter().filter(|&&x| x == value).count();
+                assert_eq!(count_eq(&u, value), expect, "count_eq len {}", len);
+            }
+        }
+
+        // long enough to overflow byte counters and any narrow sum
+        let a = vec![i8::MIN; 100_000];
+        assert_eq!(sum_i8(&a), -12_800_000);
+        let u = vec![7u8; 100_000];
+        assert_eq!(count_eq(&u, 7), 100_000);
+    }
+
+    #[test]
+    fn test_reductions_i16_i32() {
+        let mut rng = rand::thread_rng();
+
+        for len in (0..300).chain([1000, 4096, 20000]) {
+            let a: Vec<i16> = (0..len).map(|_| rng.gen()).collect();
+            let b: Vec<i16> = (0..len).map(|_| rng.gen_range(-3..3)).collect();
+            for a in [&a, &b] {
+                assert_eq!(sum_i16(a), a.iter().map(|&x| x as i64).sum::<i64>());
+                assert_eq!(min_i16(a), a.iter().copied().min());
+                assert_eq!(max_i16(a), a.iter().copied().max());
+                let m = a.iter().copied().min();
+                assert_eq!(
+                    argmin_i16(a),
+                    m.and_then(|m| a.iter().position(|&x| x == m))
+                );
+                let m = a.iter().copied().max();
+                assert_eq!(
+                    argmax_i16(a),
+                    m.and_then(|m| a.iter().position(|&x| x == m))
+                );
+            }
+
+            let a: Vec<i32> = (0..len).map(|_| rng.gen()).collect();
+            let b: Vec<i32> = (0..len).map(|_| rng.gen_range(-3..3)).collect();
+            for a in [&a, &b] {
+                assert_eq!(sum_i32(a), a.iter().map(|&x| x as i64).sum::<i64>());
+                assert_eq!(min_i32(a), a.iter().copied().min());
+                assert_eq!(max_i32(a), a.iter().copied().max());
+                let m = a.iter().copied().min();
+                assert_eq!(
+                    argmin_i32(a),
+                    m.and_then(|m| a.iter().position(|&x| x == m))
+                );
+                let m = a.iter().copied().max();
+                assert_eq!(
+                    argmax_i32(a),
+                    m.and_then(|m| a.iter().position(|&x| x == m))
+                );
+            }
+        }
+
+        // long enough to overflow the 32-bit lanes of sum_i16, and any 32-bit sum
+        let a = vec![i16::MIN; 2_000_000];
+        assert_eq!(sum_i16(&a), -32768 * 2_000_000);
+        let a = vec![i32::MIN; 100_000];
+        assert_eq!(sum_i32(&a), i32::MIN as i64 * 100_000);
+    }
+
+    #[test]
+    fn test_reductions_f32() {
+        let mut rng = rand::thread_rng();
+
+        // small whole numbers, so that the sum is exact in any order, with some NaN, infinities
+        // and zeros of both signs
+        let special = [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 0.0, -0.0];
+        for len in (0..300).chain([1000, 4096, 20000]) {
+            for p in [0.0, 0.01, 0.5, 1.0] {
+                let a: Vec<f32> = (0..len)
+                    .map(|_| {
+                        if rng.gen_bool(p) {
+                            special[rng.gen_range(0..special.len())]
+                        } else {
+                            rng.gen_range(-100..100) as f32
+                        }
+                    })
+                    .collect();
+                let sum = a.iter().sum::<f32>();
+                assert!(sum_f32(&a) == sum || sum.is_nan() && sum_f32(&a).is_nan());
+                let numbers = a.iter().copied().filter(|x| !x.is_nan());
+                let m = numbers.clone().reduce(f32::min);
+                assert_eq!(min_f32(&a), m);
+                assert_eq!(
+                    argmin_f32(&a),
+                    m.and_then(|m| a.iter().position(|&x| x == m))
+                );
+                let m = numbers.reduce(f32::max);
+                assert_eq!(max_f32(&a), m);
+                assert_eq!(
+                    argmax_f32(&a),
+                    m.and_then(|m| a.iter().position(|&x| x == m))
+                );
+            }
+        }
+
+        let a = vec![f32::NAN; 100];
+        assert_eq!(min_f32(&a), None);
+        assert_eq!(argmax_f32(&a), None);
+        let mut a = vec![f32::NAN; 100];
+        a[70] = f32::INFINITY;
+        assert_eq!(min_f32(&a), Some(f32::INFINITY));
+        assert_eq!(argmin_f32(&a), Some(70));
+        a[70] = f32::NEG_INFINITY;
+        assert_eq!(max_f32(&a), Some(f32::NEG_INFINITY));
+        assert_eq!(argmax_f32(&a), Some(70));
+    }
+}
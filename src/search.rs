@@ -0,0 +1,225 @@
+use crate::reduce::count_eq;
+use crate::vectori128::{compare_eq, horizontal_find_first, to_bits, Vec16bc, Vec16c};
+
+/// Index of the first occurrence of byte in haystack
+pub fn find_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+    let needle = Vec16c::set_value(byte as i8);
+    find_by(haystack, |v| compare_eq(v, needle), |x| x == byte)
+}
+
+/// Index of the first byte in haystack equal to either b1 or b2
+pub fn find_any_of_2(haystack: &[u8], b1: u8, b2: u8) -> Option<usize> {
+    let (n1, n2) = (Vec16c::set_value(b1 as i8), Vec16c::set_value(b2 as i8));
+    find_by(
+        haystack,
+        |v| compare_eq(v, n1) | compare_eq(v, n2),
+        |x| x == b1 || x == b2,
+    )
+}
+
+/// Index of the first byte in haystack equal to b1, b2 or b3
+pub fn find_any_of_3(haystack: &[u8], b1: u8, b2: u8, b3: u8) -> Option<usize> {
+    let (n1, n2) = (Vec16c::set_value(b1 as i8), Vec16c::set_value(b2 as i8));
+    let n3 = Vec16c::set_value(b3 as i8);
+    find_by(
+        haystack,
+        |v| compare_eq(v, n1) | compare_eq(v, n2) | compare_eq(v, n3),
+        |x| x == b1 || x == b2 || x == b3,
+    )
+}
+
+/// Index of the last occurrence of byte in haystack
+pub fn rfind_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+    let needle = Vec16c::set_value(byte as i8);
+    let mut v = Vec16c::new();
+    let chunks = haystack.rchunks_exact(16);
+    let rest = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        // SAFETY: each chunk is 16 bytes long
+        unsafe { v.load(chunk.as_ptr() as *const i8) };
+        let bits = to_bits(compare_eq(v, needle));
+        if bits != 0 {
+            let start = haystack.len() - 16 * (i + 1);
+            return Some(start + 15 - bits.leading_zeros() as usize);
+        }
+    }
+    rest.iter().rposition(|&x| x == byte)
+}
+
+/// Number of occurrences of byte in haystack
+pub fn count_byte(haystack: &[u8], byte: u8) -> usize {
+    count_eq(haystack, byte)
+}
+
+/// Index of the first occurrence of needle in haystack. An empty needle is found at 0.
+///
+/// Candidates are positions where both the first and the last byte of needle match, tested
+/// 16 positions at a time. Only candidates are compared in full
+pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let n = needle.len();
+    if n == 0 {
+        return Some(0);
+    }
+    if n > haystack.len() {
+        return None;
+    }
+    if n == 1 {
+        return find_byte(haystack, needle[0]);
+    }
+
+    let first = Vec16c::set_value(needle[0] as i8);
+    let last = Vec16c::set_value(needle[n - 1] as i8);
+    let mut vfirst = Vec16c::new();
+    let mut vlast = Vec16c::new();
+    let mut i = 0;
+    while i + n - 1 + 16 <= haystack.len() {
+        // SAFETY: i + n - 1 + 16 <= haystack.len()
+        unsafe {
+            vfirst.load(haystack.as_ptr().add(i) as *const i8);
+            vlast.load(haystack.as_ptr().add(i + n - 1) as *const i8);
+        }
+        let mut bits = to_bits(compare_eq(vfirst, first) & compare_eq(vlast, last));
+        while bits != 0 {
+            let j = i + bits.trailing_zeros() as usize;
+            if haystack[j + 1..j + n - 1] == needle[1..n - 1] {
+                return Some(j);
+            }
+            bits &= bits - 1;
+        }
+        i += 16;
+    }
+    haystack[i..]
+        .windows(n)
+        .position(|w| w == needle)
+        .map(|j| i + j)
+}
+
+/// Scan 16 bytes at a time with a vector predicate, and the remaining bytes with a scalar one
+fn find_by<V, S>(haystack: &[u8], vector: V, scalar: S) -> Option<usize>
+where
+    V: Fn(Vec16c) -> Vec16bc,
+    S: Fn(u8) -> bool,
+{
+    let mut v = Vec16c::new();
+    let chunks = haystack.chunks_exact(16);
+    let rest = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        // SAFETY: each chunk is 16 bytes long
+        unsafe { v.load(chunk.as_ptr() as *const i8) };
+        if let Some(j) = horizontal_find_first(vector(v)) {
+            return Some(16 * i + j);
+        }
+    }
+    rest.iter()
+        .position(|&x| scalar(x))
+        .map(|j| haystack.len() - rest.len() + j)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn find_scalar(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        if needle.is_empty() {
+            return Some(0);
+        }
+        haystack.windows(needle.len()).position(|w| w == needle)
+    }
+
+    fn check(haystack: &[u8]) {
+        for b in 0..4 {
+            assert_eq!(
+                find_byte(haystack, b),
+                haystack.iter().position(|&x| x == b)
+            );
+            assert_eq!(
+                rfind_byte(haystack, b),
+                haystack.iter().rposition(|&x| x == b)
+            );
+            assert_eq!(
+                count_byte(haystack, b),
+                haystack.iter().filter(|&&x| x == b).count()
+            );
+            assert_eq!(
+                find_any_of_2(haystack, b, 3),
+                haystack.iter().position(|&x| x == b || x == 3)
+            );
+            assert_eq!(
+                find_any_of_3(haystack, b, 2, 3),
+                haystack.iter().position(|&x| x == b || x == 2 || x == 3)
+            );
+        }
+    }
+
+    #[test]
+    fn test_byte_search_exhaustive() {
+        // every haystack up to 8 bytes over a 3-letter alphabet
+        for len in 0..=8u32 {
+            for code in 0..3usize.pow(len) {
+                let haystack: Vec<u8> =
+                    (0..len).map(|i| (code / 3usize.pow(i) % 3) as u8).collect();
+                check(&haystack);
+            }
+        }
+        // a single match at every position of longer haystacks
+        for len in 0..100 {
+            for pos in 0..len {
+                let mut haystack = vec![0u8; len];
+                haystack[pos] = 1;
+                check(&haystack);
+            }
+        }
+    }
+
+    #[test]
+    fn test_substring_exhaustive() {
+        // every haystack up to 7 bytes against every needle up to 3 bytes over a 2-letter alphabet
+        let all = |len: u32| -> Vec<Vec<u8>> {
+            (0..1usize << len)
+                .map(|code| (0..len).map(|i| (code >> i & 1) as u8).collect())
+                .collect()
+        };
+        for hlen in 0..=7 {
+            for haystack in all(hlen) {
+                for nlen in 0..=3 {
+                    for needle in all(nlen) {
+                        assert_eq!(find(&haystack, &needle), find_scalar(&haystack, &needle));
+                    }
+                }
+            }
+        }
+        // a single match at every position of longer haystacks
+        let needle = [1, 2, 3, 1];
+        for len in 0..100usize {
+            for pos in 0..(len + 1).saturating_sub(4) {
+                let mut haystack = vec![1u8; len];
+                haystack[pos..pos + 4].copy_from_slice(&needle);
+                assert_eq!(find(&haystack, &needle), Some(pos));
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_random() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..2000 {
+            let len = rng.gen_range(0..300);
+            let alphabet = rng.gen_range(1..6);
+            let haystack: Vec<u8> = (0..len).map(|_| rng.gen_range(0..alphabet)).collect();
+            check(&haystack);
+
+            let nlen = rng.gen_range(0..8);
+            let needle: Vec<u8> = (0..nlen).map(|_| rng.gen_range(0..alphabet)).collect();
+            assert_eq!(find(&haystack, &needle), find_scalar(&haystack, &needle));
+            if len > 0 {
+                // a needle taken from the haystack is always found
+                let start = rng.gen_range(0..len);
+                let end = rng.gen_range(start..=len.min(start + 40));
+                let needle = &haystack[start..end];
+                assert_eq!(find(&haystack, needle), find_scalar(&haystack, needle));
+            }
+        }
+    }
+}
@@ -0,0 +1,625 @@
+use crate::vectori128::{self, Vec16bc, Vec16c};
+use core::fmt::Debug;
+use core::ops;
+
+/// A vector of LANES elements of type Elem, for code that is generic over the vector type.
+/// The traits forward to the inherent methods and free functions of each vector class.
+/// Vec16c is the only vector class so far, and Lane is a vector of one element
+pub trait SimdVector: Copy + PartialEq + Debug {
+    type Elem: Copy + Default;
+    type Mask: SimdMask;
+
+    const LANES: usize;
+
+    /// Broadcast a value into all elements
+    fn splat(value: Self::Elem) -> Self;
+
+    /// Load the first LANES elements of a slice. Panics if the slice is too short
+    fn load_slice(src: &[Self::Elem]) -> Self;
+
+    /// Store into the first LANES elements of a slice. Panics if the slice is too short
+    fn store_slice(self, dst: &mut [Self::Elem]);
+
+    /// Elementwise mask ? a : b
+    fn select(mask: Self::Mask, a: Self, b: Self) -> Self;
+}
+
+/// A boolean vector, as produced by the compare functions
+pub trait SimdMask:
+    Copy
+    + ops::BitAnd<Output = Self>
+    + ops::BitOr<Output = Self>
+    + ops::BitXor<Output = Self>
+    + ops::Not<Output = Self>
+{
+    const LANES: usize;
+
+    /// Set all elements to the same value
+    fn splat(value: bool) -> Self;
+
+    /// Bit i is element i
+    fn to_bits(self) -> u64;
+
+    /// At least one element is true
+    fn any(self) -> bool {
+        self.to_bits() != 0
+    }
+
+    /// All elements are true
+    fn all(self) -> bool {
+        self.to_bits() == u64::MAX >> (64 - Self::LANES)
+    }
+
+    /// Number of true elements
+    fn count(self) -> u32 {
+        self.to_bits().count_ones()
+    }
+
+    /// Index of the first true element
+    fn find_first(self) -> Option<usize> {
+        let bits = self.to_bits();
+        (bits != 0).then(|| bits.trailing_zeros() as usize)
+    }
+}
+
+/// A vector of integers. Arithmetic wraps around, as with the operators of the vector classes
+pub trait SimdInt:
+    SimdVector
+    + ops::Add<Output = Self>
+    + ops::Sub<Output = Self>
+    + ops::Mul<Output = Self>
+    + ops::Neg<Output = Self>
+    + ops::AddAssign
+    + ops::SubAssign
+    + ops::MulAssign
+{
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn abs(self) -> Self;
+    fn add_saturated(self, other: Self) -> Self;
+    fn sub_saturated(self, other: Self) -> Self;
+
+    fn simd_eq(self, other: Self) -> Self::Mask;
+    fn simd_lt(self, other: Self) -> Self::Mask;
+    fn simd_gt(self, other: Self) -> Self::Mask;
+
+    /// Sum of all elements. Overflow will wrap around
+    fn horizontal_add(self) -> Self::Elem;
+    fn horizontal_min(self) -> Self::Elem;
+    fn horizontal_max(self) -> Self::Elem;
+}
+
+/// A vector of floating point numbers. min and max return the other operand where one of them
+/// is NaN, as f32::min and f32::max do, and the compares are false for NaN
+pub trait SimdFloat:
+    SimdVector
+    + ops::Add<Output = Self>
+    + ops::Sub<Output = Self>
+    + ops::Mul<Output = Self>
+    + ops::Div<Output = Self>
+    + ops::Neg<Output = Self>
+    + ops::AddAssign
+    + ops::SubAssign
+    + ops::MulAssign
+    + ops::DivAssign
+{
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn abs(self) -> Self;
+
+    fn simd_eq(self, other: Self) -> Self::Mask;
+    fn simd_lt(self, other: Self) -> Self::Mask;
+    fn simd_gt(self, other: Self) -> Self::Mask;
+
+    /// True for the NaN elements
+    fn is_nan(self) -> Self::Mask;
+
+    /// Sum of all elements
+    fn horizontal_add(self) -> Self::Elem;
+    /// Smallest element, ignoring NaN. NaN if all elements are NaN
+    fn horizontal_min(self) -> Self::Elem;
+    /// Largest element, ignoring NaN. NaN if all elements are NaN
+    fn horizontal_max(self) -> Self::Elem;
+}
+
+impl SimdVector for Vec16c {
+    type Elem = i8;
+    type Mask = Vec16bc;
+
+    const LANES: usize = 16;
+
+    #[inline]
+    fn splat(value: i8) -> Self {
+        Vec16c::set_value(value)
+    }
+
+    #[inline]
+    fn load_slice(src: &[i8]) -> Self {
+        Vec16c::set_values(src[..16].try_into().unwrap())
+    }
+
+    #[inline]
+    fn store_slice(self, dst: &mut [i8]) {
+        let dst = &mut dst[..16];
+        // SAFETY: dst has room for 16 elements
+        unsafe { self.store(dst.as_mut_ptr()) }
+    }
+
+    #[inline]
+    fn select(mask: Vec16bc, a: Self, b: Self) -> Self {
+        vectori128::select(mask, a, b)
+    }
+}
+
+impl SimdMask for Vec16bc {
+    const LANES: usize = 16;
+
+    #[inline]
+    fn splat(value: bool) -> Self {
+        vectori128::compare_eq(Vec16c::new(), Vec16c::set_value(!value as i8))
+    }
+
+    #[inline]
+    fn to_bits(self) -> u64 {
+        vectori128::to_bits(self) as u64
+    }
+
+    #[inline]
+    fn count(self) -> u32 {
+        vectori128::horizontal_count(self)
+    }
+
+    #[inline]
+    fn find_first(self) -> Option<usize> {
+        vectori128::horizontal_find_first(self)
+    }
+}
+
+impl SimdInt for Vec16c {
+    #[inline]
+    fn min(self, other: Self) -> Self {
+        vectori128::min(self, other)
+    }
+
+    #[inline]
+    fn max(self, other: Self) -> Self {
+        vectori128::max(self, other)
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        vectori128::abs(self)
+    }
+
+    #[inline]
+    fn add_saturated(self, other: Self) -> Self {
+        vectori128::add_saturated(self, other)
+    }
+
+    #[inline]
+    fn sub_saturated(self, other: Self) -> Self {
+        vectori128::sub_saturated(self, other)
+    }
+
+    #[inline]
+    fn simd_eq(self, other: Self) -> Vec16bc {
+        vectori128::compare_eq(self, other)
+    }
+
+    #[inline]
+    fn simd_lt(self, other: Self) -> Vec16bc {
+        vectori128::compare_lt(self, other)
+    }
+
+    #[inline]
+    fn simd_gt(self, other: Self) -> Vec16bc {
+        vectori128::compare_gt(self, other)
+    }
+
+    #[inline]
+    fn horizontal_add(self) -> i8 {
+        vectori128::horizontal_add(self) as i8
+    }
+
+    #[inline]
+    fn horizontal_min(self) -> i8 {
+        vectori128::horizontal_min(self)
+    }
+
+    #[inline]
+    fn horizontal_max(self) -> i8 {
+        vectori128::horizontal_max(self)
+    }
+}
+
+/// A single number as a vector of one element, with Lane<bool> as the mask. Generic code can
+/// then also run one element at a time, for example on the end of a slice that does not fill a
+/// vector. Integer arithmetic wraps around, as in the vector classes. The traits are
+/// implemented on this type and not on the numbers themselves, so that their methods do not
+/// collide with the methods of the numbers, such as Ord::min
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Lane<T>(pub T);
+
+impl SimdMask for Lane<bool> {
+    const LANES: usize = 1;
+
+    #[inline]
+    fn splat(value: bool) -> Self {
+        Lane(value)
+    }
+
+    #[inline]
+    fn to_bits(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+macro_rules! impl_lane_op {
+    ($elem:ty, $op:ident, $f:ident, $op_assign:ident, $f_assign:ident, $g:expr) => {
+        impl ops::$op for Lane<$elem> {
+            type Output = Self;
+
+            #[inline]
+            fn $f(self, other: Self) -> Self {
+                Lane($g(self.0, other.0))
+            }
+        }
+
+        impl ops::$op_assign for Lane<$elem> {
+            #[inline]
+            fn $f_assign(&mut self, other: Self) {
+                *self = ops::$op::$f(*self, other);
+            }
+        }
+    };
+}
+
+impl_lane_op!(bool, BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a
+    & b);
+impl_lane_op!(bool, BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+impl_lane_op!(bool, BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| a
+    ^ b);
+
+impl ops::Not for Lane<bool> {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Self {
+        Lane(!self.0)
+    }
+}
+
+macro_rules! impl_lane_vector {
+    ($elem:ty, $neg:expr) => {
+        impl SimdVector for Lane<$elem> {
+            type Elem = $elem;
+            type Mask = Lane<bool>;
+
+            const LANES: usize = 1;
+
+            #[inline]
+            fn splat(value: $elem) -> Self {
+                Lane(value)
+            }
+
+            #[inline]
+            fn load_slice(src: &[$elem]) -> Self {
+                Lane(src[0])
+            }
+
+            #[inline]
+            fn store_slice(self, dst: &mut [$elem]) {
+                dst[0] = self.0;
+            }
+
+            #[inline]
+            fn select(mask: Lane<bool>, a: Self, b: Self) -> Self {
+                if mask.0 {
+                    a
+                } else {
+                    b
+                }
+            }
+        }
+
+        impl ops::Neg for Lane<$elem> {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self {
+                Lane($neg(self.0))
+            }
+        }
+    };
+}
+
+macro_rules! impl_lane_int {
+    ($elem:ty) => {
+        impl_lane_vector!($elem, <$elem>::wrapping_neg);
+        impl_lane_op!(
+            $elem,
+            Add,
+            add,
+            AddAssign,
+            add_assign,
+            <$elem>::wrapping_add
+        );
+        impl_lane_op!(
+            $elem,
+            Sub,
+            sub,
+            SubAssign,
+            sub_assign,
+            <$elem>::wrapping_sub
+        );
+        impl_lane_op!(
+            $elem,
+            Mul,
+            mul,
+            MulAssign,
+            mul_assign,
+            <$elem>::wrapping_mul
+        );
+
+        impl SimdInt for Lane<$elem> {
+            #[inline]
+            fn min(self, other: Self) -> Self {
+                Lane(Ord::min(self.0, other.0))
+            }
+
+            #[inline]
+            fn max(self, other: Self) -> Self {
+                Lane(Ord::max(self.0, other.0))
+            }
+
+            #[inline]
+            fn abs(self) -> Self {
+                Lane(self.0.wrapping_abs())
+            }
+
+            #[inline]
+            fn add_saturated(self, other: Self) -> Self {
+                Lane(self.0.saturating_add(other.0))
+            }
+
+            #[inline]
+            fn sub_saturated(self, other: Self) -> Self {
+                Lane(self.0.saturating_sub(other.0))
+            }
+
+            #[inline]
+            fn simd_eq(self, other: Self) -> Lane<bool> {
+                Lane(self.0 == other.0)
+            }
+
+            #[inline]
+            fn simd_lt(self, other: Self) -> Lane<bool> {
+                Lane(self.0 < other.0)
+            }
+
+            #[inline]
+            fn simd_gt(self, other: Self) -> Lane<bool> {
+                Lane(self.0 > other.0)
+            }
+
+            #[inline]
+            fn horizontal_add(self) -> $elem {
+                self.0
+            }
+
+            #[inline]
+            fn horizontal_min(self) -> $elem {
+                self.0
+            }
+
+            #[inline]
+            fn horizontal_max(self) -> $elem {
+                self.0
+            }
+        }
+    };
+}
+
+impl_lane_int!(i8);
+impl_lane_int!(i16);
+impl_lane_int!(i32);
+impl_lane_int!(i64);
+
+macro_rules! impl_lane_float {
+    ($elem:ty) => {
+        impl_lane_vector!($elem, |x: $elem| -x);
+        impl_lane_op!($elem, Add, add, AddAssign, add_assign, |a, b| a + b);
+        impl_lane_op!($elem, Sub, sub, SubAssign, sub_assign, |a, b| a - b);
+        impl_lane_op!($elem, Mul, mul, MulAssign, mul_assign, |a, b| a * b);
+        impl_lane_op!($elem, Div, div, DivAssign, div_assign, |a, b| a / b);
+
+        impl SimdFloat for Lane<$elem> {
+            #[inline]
+            fn min(self, other: Self) -> Self {
+                Lane(self.0.min(other.0))
+            }
+
+            #[inline]
+            fn max(self, other: Self) -> Self {
+                Lane(self.0.max(other.0))
+            }
+
+            #[inline]
+            fn abs(self) -> Self {
+                Lane(self.0.abs())
+            }
+
+            #[inline]
+            fn simd_eq(self, other: Self) -> Lane<bool> {
+                Lane(self.0 == other.0)
+            }
+
+            #[inline]
+            fn simd_lt(self, other: Self) -> Lane<bool> {
+                Lane(self.0 < other.0)
+            }
+
+            #[inline]
+            fn simd_gt(self, other: Self) -> Lane<bool> {
+                Lane(self.0 > other.0)
+            }
+
+            #[inline]
+            fn is_nan(self) -> Lane<bool> {
+                Lane(self.0.is_nan())
+            }
+
+            #[inline]
+            fn horizontal_add(self) -> $elem {
+                self.0
+            }
+
+            #[inline]
+            fn horizontal_min(self) -> $elem {
+                self.0
+            }
+
+            #[inline]
+            fn horizontal_max(self) -> $elem {
+                self.0
+            }
+        }
+    };
+}
+
+impl_lane_float!(f32);
+impl_lane_float!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// Clamp each element to [lo, hi], then return the clamped vectors, the number of elements
+    /// that were changed and the largest absolute value. Written once for any integer vector
+    fn clamp_all<V: SimdInt>(data: &mut [V::Elem], lo: V::Elem, hi: V::Elem) -> (u32, V) {
+        let (lo, hi) = (V::splat(lo), V::splat(hi));
+        let mut changed = 0;
+        let mut largest = V::splat(V::Elem::default());
+        for chunk in data.chunks_exact_mut(V::LANES) {
+            let v = V::load_slice(chunk);
+            let c = v.max(lo).min(hi);
+            changed += (!c.simd_eq(v)).count();
+            largest = largest.max(v.abs());
+            V::select(v.simd_gt(hi), hi, c).store_slice(chunk);
+        }
+        (changed, largest)
+    }
+
+    #[test]
+    fn test_generic_clamp() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            let data: Vec<i8> = (0..64).map(|_| rng.gen()).collect();
+            let (lo, hi) = (rng.gen_range(-100..0), rng.gen_range(0..100));
+
+            // the same function on 16 and on 1 element at a time
+            let mut clamped = data.clone();
+            let (changed, largest) = clamp_all::<Vec16c>(&mut clamped, lo, hi);
+            let mut clamped_1 = data.clone();
+            let (changed_1, largest_1) = clamp_all::<Lane<i8>>(&mut clamped_1, lo, hi);
+            assert_eq!(clamped_1, clamped);
+            assert_eq!(changed_1, changed);
+            assert_eq!(largest_1.horizontal_max(), largest.horizontal_max());
+
+            let expected: Vec<i8> = data.iter().map(|&x| x.clamp(lo, hi)).collect();
+            assert_eq!(clamped, expected);
+            let n = data.iter().zip(&expected).filter(|(x, y)| x != y).count();
+            assert_eq!(changed as usize, n);
+            let abs_max = data.iter().map(|x| x.wrapping_abs()).max().unwrap();
+            assert_eq!(largest.horizontal_max(), abs_max);
+
+            // and on other element types
+            let wide: Vec<i32> = data.iter().map(|&x| i32::from(x) << 20).collect();
+            let mut clamped_32 = wide.clone();
+            let (lo, hi) = (i32::from(lo) << 20, i32::from(hi) << 20);
+            clamp_all::<Lane<i32>>(&mut clamped_32, lo, hi);
+            let expected: Vec<i32> = wide.iter().map(|&x| x.clamp(lo, hi)).collect();
+            assert_eq!(clamped_32, expected);
+        }
+    }
+
+    /// Replace NaN by 0 and scale to the largest absolute value, returning the sum before
+    /// scaling. Written once for any float vector
+    fn normalize<V: SimdFloat>(data: &mut [V::Elem]) -> V::Elem {
+        let zero = V::splat(V::Elem::default());
+        let mut largest = zero;
+        let mut sum = zero;
+        for chunk in data.chunks_exact_mut(V::LANES) {
+            let v = V::load_slice(chunk);
+            let v = V::select(v.is_nan(), zero, v);
+            sum += v;
+            largest = largest.max(v.abs());
+            v.store_slice(chunk);
+        }
+        let largest = V::splat(largest.horizontal_max());
+        for chunk in data.chunks_exact_mut(V::LANES) {
+            let v = V::load_slice(chunk);
+            V::select(largest.simd_gt(zero), v / largest, v).store_slice(chunk);
+        }
+        sum.horizontal_add()
+    }
+
+    #[test]
+    fn test_generic_float() {
+        let mut x = [1.5f32, f32::NAN, -3.0, 0.75];
+        assert_eq!(normalize::<Lane<f32>>(&mut x), -0.75);
+        assert_eq!(x, [0.5, 0.0, -1.0, 0.25]);
+        let mut y = [0.0f64, -0.0, f64::NAN];
+        assert_eq!(normalize::<Lane<f64>>(&mut y), 0.0);
+        assert_eq!(y, [0.0; 3]);
+
+        let nan = Lane(f32::NAN);
+        assert_eq!(nan.min(Lane(1.0)), Lane(1.0));
+        assert_eq!(Lane(2.0).max(Lane(f64::NAN)), Lane(2.0));
+        assert!(!nan.simd_eq(nan).0 && nan.is_nan().0);
+        assert!(!nan.simd_lt(Lane(1.0)).0 && !nan.simd_gt(Lane(1.0)).0);
+        assert_eq!(-Lane(1.5f64) / Lane(0.5), Lane(-3.0));
+    }
+
+    #[test]
+    fn test_traits() {
+        let a = Vec16c::load_slice(&[
+            1, -2, 3, -4, 5, -6, 7, -8, 9, -10, 11, -12, 13, -14, 15, -16,
+        ]);
+        let mut out = [0i8; 17];
+        (a * Vec16c::splat(2)).store_slice(&mut out[1..]);
+        assert_eq!(out[1..5], [2, -4, 6, -8]);
+        assert_eq!(out[0], 0);
+        assert_eq!(a.horizontal_add(), -8);
+        assert_eq!(a.horizontal_min(), -16);
+        assert_eq!(a.horizontal_max(), 15);
+
+        let neg = a.simd_lt(Vec16c::splat(0));
+        assert_eq!(neg.to_bits(), 0xAAAA);
+        assert_eq!(neg.count(), 8);
+        assert_eq!(neg.find_first(), Some(1));
+        assert!(neg.any() && !neg.all());
+        assert!((neg | !neg).all());
+        assert!(!(neg & !neg).any());
+        assert!(Vec16bc::splat(true).all());
+        assert_eq!(Vec16bc::splat(false).find_first(), None);
+        assert_eq!(<Vec16c as SimdVector>::LANES, 16);
+
+        let w = Lane(i16::MAX);
+        assert_eq!(w + Lane(1), Lane(i16::MIN));
+        assert_eq!(w.add_saturated(Lane(1)), w);
+        assert_eq!(Lane(i64::MIN).abs(), Lane(i64::MIN));
+        assert_eq!(-Lane(i8::MIN) * Lane(3), Lane(i8::MIN));
+        assert!(Lane(true).all() && !Lane(false).any());
+        assert_eq!((Lane(true) ^ Lane(true)).find_first(), None);
+        assert_eq!(<Lane<i32> as SimdVector>::LANES, 1);
+
+        // the methods of the numbers are not hidden by the traits
+        use core::num::Wrapping;
+        assert_eq!(Wrapping(1i8).min(Wrapping(2)), Wrapping(1));
+        assert_eq!(core::hint::black_box(3i32).max(4), 4);
+        assert_eq!(f32::NAN.min(1.0), 1.0);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::vectori128::Vec16c;
+
+/// Apply f to src, 16 elements at a time: dst[i] = f(src)[i].
+/// The last partial vector is padded with zeros, and the padding lanes of the result are discarded
+///
+/// Panics if the slices have different lengths
+pub fn map<F>(src: &[i8], dst: &mut [i8], mut f: F)
+where
+    F: FnMut(Vec16c) -> Vec16c,
+{
+    apply([src], dst, false, |[a]| f(a));
+}
+
+/// Apply f to a and b, 16 elements at a time: dst[i] = f(a, b)[i]
+///
+/// Panics if the slices have different lengths
+pub fn zip_map<F>(a: &[i8], b: &[i8], dst: &mut [i8], mut f: F)
+where
+    F: FnMut(Vec16c, Vec16c) -> Vec16c,
+{
+    apply([a, b], dst, false, |[a, b]| f(a, b));
+}
+
+/// Apply f to a, b and c, 16 elements at a time: dst[i] = f(a, b, c)[i]
+///
+/// Panics if the slices have different lengths
+pub fn zip3_map<F>(a: &[i8], b: &[i8], c: &[i8], dst: &mut [i8], mut f: F)
+where
+    F: FnMut(Vec16c, Vec16c, Vec16c) -> Vec16c,
+{
+    apply([a, b, c], dst, false, |[a, b, c]| f(a, b, c));
+}
+
+/// Same as map, but first processes a partial vector so that the remaining loads from src
+/// are aligned by 16
+pub fn map_aligned<F>(src: &[i8], dst: &mut [i8], mut f: F)
+where
+    F: FnMut(Vec16c) -> Vec16c,
+{
+    apply([src], dst, true, |[a]| f(a));
+}
+
+/// Same as zip_map, but first processes a partial vector so that the remaining loads from a
+/// are aligned by 16. b and dst get aligned accesses too if they have the same offset as a
+pub fn zip_map_aligned<F>(a: &[i8], b: &[i8], dst: &mut [i8], mut f: F)
+where
+    F: FnMut(Vec16c, Vec16c) -> Vec16c,
+{
+    apply([a, b], dst, true, |[a, b]| f(a, b));
+}
+
+/// Same as zip3_map, but first processes a partial vector so that the remaining loads from a
+/// are aligned by 16. b, c and dst get aligned accesses too if they have the same offset as a
+pub fn zip3_map_aligned<F>(a: &[i8], b: &[i8], c: &[i8], dst: &mut [i8], mut f: F)
+where
+    F: FnMut(Vec16c, Vec16c, Vec16c) -> Vec16c,
+{
+    apply([a, b, c], dst, true, |[a, b, c]| f(a, b, c));
+}
+
+/// Main loop shared by all the kernels: optional unaligned head, full vectors, partial tail
+fn apply<const K: usize, F>(src: [&[i8]; K], dst: &mut [i8], align_head: bool, mut f: F)
+where
+    F: FnMut([Vec16c; K]) -> Vec16c,
+{
+    let n = dst.len();
+    for s in src.iter() {
+        assert_eq!(s.len(), n, "all slices must have the same length");
+    }
+
+    let head = if align_head && K > 0 {
+        src[0].as_ptr().align_offset(16).min(n)
+    } else {
+        0
+    };
+    if head > 0 {
+        apply_partial(&src, dst, 0, head, &mut f);
+    }
+
+    let aligned: [bool; K] =
+        core::array::from_fn(|s| src[s][head..].as_ptr().align_offset(16) == 0);
+    let dst_aligned = dst[head..].as_ptr().align_offset(16) == 0;
+    let mut i = head;
+    while i + 16 <= n {
+        let v: [Vec16c; K] = core::array::from_fn(|s| {
+            let mut v = Vec16c::new();
+            // SAFETY: i + 16 <= n, and alignment was checked above
+            unsafe {
+                let p = src[s].as_ptr().add(i);
+                if aligned[s] {
+                    v.load_aligned(p);
+                } else {
+                    v.load(p);
+                }
+            }
+            v
+        });
+        let r = f(v);
+        // SAFETY: i + 16 <= n, and alignment was checked above
+        unsafe {
+            let p = dst.as_mut_ptr().add(i);
+            if dst_aligned {
+                r.store_aligned(p);
+            } else {
+                r.store(p);
+            }
+        }
+        i += 16;
+    }
+
+    if i < n {
+        apply_partial(&src, dst, i, n, &mut f);
+    }
+}
+
+/// Process elements start..end, fewer than 16, through zero-padded buffers
+fn apply_partial<const K: usize, F>(
+    src: &[&[i8]; K],
+    dst: &mut [i8],
+    start: usize,
+    end: usize,
+    f: &mut F,
+) where
+    F: FnMut([Vec16c; K]) -> Vec16c,
+{
+    let len = end - start;
+    let v: [Vec16c; K] = core::array::from_fn(|s| {
+        let mut x: [i8; 16] = [0; 16];
+        x[..len].copy_from_slice(&src[s][start..end]);
+        Vec16c::set_values(x)
+    });
+    let mut x: [i8; 16] = [0; 16];
+    // SAFETY: x has room for 16 elements
+    unsafe { f(v).store(&mut x as *mut i8) };
+    dst[start..end].copy_from_slice(&x[..len]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vectori128::max;
+    use rand::Rng;
+
+    #[test]
+    fn test_slice_maps() {
+        let mut rng = rand::thread_rng();
+        let a: Vec<i8> = (0..200).map(|_| rng.gen()).collect();
+        let b: Vec<i8> = (0..200).map(|_| rng.gen()).collect();
+        let c: Vec<i8> = (0..200).map(|_| rng.gen()).collect();
+
+        // all lengths around a few vectors, at every offset from an aligned start
+        for offset in 0..16 {
+            for len in 0..70 {
+                let a = &a[offset..offset + len];
+                let b = &b[offset + 1..offset + 1 + len];
+                let c = &c[offset..offset + len];
+                let mut dst = vec![0i8; len + 3];
+                let dst = &mut dst[3..];
+
+                let neg: Vec<i8> = a.iter().map(|x| x.wrapping_neg()).collect();
+                map(a, dst, |x| -x);
+                assert_eq!(dst, &neg[..]);
+                dst.fill(0);
+                map_aligned(a, dst, |x| -x);
+                assert_eq!(dst, &neg[..]);
+
+                let prod: Vec<i8> = a.iter().zip(b).map(|(x, y)| x.wrapping_mul(*y)).collect();
+                zip_map(a, b, dst, |x, y| x * y);
+                assert_eq!(dst, &prod[..]);
+                dst.fill(0);
+                zip_map_aligned(a, b, dst, |x, y| x * y);
+                assert_eq!(dst, &prod[..]);
+
+                let expect: Vec<i8> = (0..len)
+                    .map(|i| a[i].wrapping_add(b[i]).max(c[i]))
+                    .collect();
+                zip3_map(a, b, c, dst, |x, y, z| max(x + y, z));
+                assert_eq!(dst, &expect[..]);
+                dst.fill(0);
+                zip3_map_aligned(a, b, c, dst, |x, y, z| max(x + y, z));
+                assert_eq!(dst, &expect[..]);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_slice_length_mismatch() {
+        let mut dst = [0i8; 3];
+        zip_map(&[1, 2, 3], &[1, 2], &mut dst, |x, y| x + y);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::instrset::has_sse42;
+use crate::vectori128::Vec16c;
+use core::arch::x86_64::*;
+
+// The string instructions need SSE4.2. When the build does not enable it, the helpers check for
+// it at run time and fall back to scalar loops without it, and the cmp functions panic.
+
+// Mode flags for the SSE4.2 string instructions, combined with | into the MODE parameter.
+// Elements are always bytes. Exactly one of the aggregation operations must be given
+
+/// Compare elements as signed instead of unsigned bytes
+pub const SIGNED: i32 = _SIDD_SBYTE_OPS;
+/// Aggregation: element i of b matches if it equals any element of a
+pub const EQUAL_ANY: i32 = _SIDD_CMP_EQUAL_ANY;
+/// Aggregation: element i of b matches if it is within any range a[2j]..=a[2j + 1]
+pub const RANGES: i32 = _SIDD_CMP_RANGES;
+/// Aggregation: element i of b matches if it equals element i of a
+pub const EQUAL_EACH: i32 = _SIDD_CMP_EQUAL_EACH;
+/// Aggregation: element i of b matches if the string a starts there (substring search)
+pub const EQUAL_ORDERED: i32 = _SIDD_CMP_EQUAL_ORDERED;
+/// Invert the result for all elements
+pub const NEGATIVE_POLARITY: i32 = _SIDD_NEGATIVE_POLARITY;
+/// Invert the result for the valid elements of b only
+pub const MASKED_NEGATIVE_POLARITY: i32 = _SIDD_MASKED_NEGATIVE_POLARITY;
+/// Index functions return the last match instead of the first
+pub const MOST_SIGNIFICANT: i32 = _SIDD_MOST_SIGNIFICANT;
+
+/// Check at compile time that MODE is a valid byte mode
+const fn check_mode(mode: i32) {
+    assert!(mode >= 0 && mode <= 0x7F, "invalid string mode flags");
+    assert!(
+        mode & _SIDD_UWORD_OPS == 0,
+        "only byte elements are supported"
+    );
+}
+
+/// Compare strings of implicit length (pcmpistri). Each string ends before its first zero element.
+/// Returns the index of the first match in b, or 16 if there is none.
+/// Panics if the CPU does not have SSE4.2
+#[inline]
+pub fn cmpistri<const MODE: i32>(a: Vec16c, b: Vec16c) -> usize {
+    const { check_mode(MODE) };
+    assert!(sse42(), "the string instructions need SSE4.2");
+    // SAFETY: the CPU has SSE4.2
+    unsafe { cmpistri_sse42::<MODE>(a, b) }
+}
+
+/// cmpistri without the check for SSE4.2
+#[inline]
+#[target_feature(enable = "sse4.2")]
+unsafe fn cmpistri_sse42<const MODE: i32>(a: Vec16c, b: Vec16c) -> usize {
+    _mm_cmpistri::<MODE>(a.xmm, b.xmm) as usize
+}
+
+/// Compare strings of explicit length (pcmpestri), using the first la elements of a
+/// and the first lb elements of b. Returns the index of the first match in b, or 16 if there is none.
+/// Panics if the CPU does not have SSE4.2
+#[inline]
+pub fn cmpestri<const MODE: i32>(a: Vec16c, la: usize, b: Vec16c, lb: usize) -> usize {
+    const { check_mode(MODE) };
+    assert!(sse42(), "the string instructions need SSE4.2");
+    // SAFETY: the CPU has SSE4.2
+    unsafe { cmpestri_sse42::<MODE>(a, la, b, lb) }
+}
+
+/// cmpestri without the check for SSE4.2
+#[inline]
+#[target_feature(enable = "sse4.2")]
+unsafe fn cmpestri_sse42<const MODE: i32>(a: Vec16c, la: usize, b: Vec16c, lb: usize) -> usize {
+    let la = la.min(16) as i32;
+    let lb = lb.min(16) as i32;
+    _mm_cmpestri::<MODE>(a.xmm, la, b.xmm, lb) as usize
+}
+
+/// Compare strings of explicit length (pcmpestrm), using the first la elements of a
+/// and the first lb elements of b. Returns the matches in b as bits, bit i corresponding to element i.
+/// Panics if the CPU does not have SSE4.2
+#[inline]
+pub fn cmpestrm<const MODE: i32>(a: Vec16c, la: usize, b: Vec16c, lb: usize) -> u16 {
+    const { check_mode(MODE) };
+    assert!(sse42(), "the string instructions need SSE4.2");
+    // SAFETY: the CPU has SSE4.2
+    unsafe { cmpestrm_sse42::<MODE>(a, la, b, lb) }
+}
+
+/// cmpestrm without the check for SSE4.2
+#[inline]
+#[target_feature(enable = "sse4.2")]
+unsafe fn cmpestrm_sse42<const MODE: i32>(a: Vec16c, la: usize, b: Vec16c, lb: usize) -> u16 {
+    let la = la.min(16) as i32;
+    let lb = lb.min(16) as i32;
+    let m = _mm_cmpestrm::<MODE>(a.xmm, la, b.xmm, lb);
+    // bit 6 (MOST_SIGNIFICANT) selects a byte mask instead of a bit mask for pcmpestrm
+    if MODE & _SIDD_UNIT_MASK != 0 {
+        _mm_movemask_epi8(m) as u16
+    } else {
+        _mm_cvtsi128_si32(m) as u16
+    }
+}
+
+/// SSE4.2 is enabled for the build or detected at run time
+#[inline]
+fn sse42() -> bool {
+    cfg!(target_feature = "sse4.2") || has_sse42()
+}
+
+/// Index of the first byte of haystack that is contained in set
+pub fn find_first_in_set(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    if sse42() {
+        // SAFETY: the CPU has SSE4.2
+        unsafe { find_first_in_set_sse42(haystack, set) }
+    } else {
+        find_first_in_set_scalar(haystack, set)
+    }
+}
+
+#[target_feature(enable = "sse4.2")]
+unsafe fn find_first_in_set_sse42(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    find_by(haystack, |b, lb| unsafe { in_set(set, b, lb) })
+}
+
+fn find_first_in_set_scalar(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    haystack.iter().position(|x| set.contains(x))
+}
+
+/// Index of the first byte of haystack that is outside all the inclusive ranges (low, high)
+pub fn find_first_not_in_ranges(haystack: &[u8], ranges: &[(u8, u8)]) -> Option<usize> {
+    if sse42() {
+        // SAFETY: the CPU has SSE4.2
+        unsafe { find_first_not_in_ranges_sse42(haystack, ranges) }
+    } else {
+        find_first_not_in_ranges_scalar(haystack, ranges)
+    }
+}
+
+#[target_feature(enable = "sse4.2")]
+unsafe fn find_first_not_in_ranges_sse42(haystack: &[u8], ranges: &[(u8, u8)]) -> Option<usize> {
+    find_by(haystack, |b, lb| {
+        // up to 8 ranges per operand
+        let inside = ranges.chunks(8).fold(0, |m, chunk| {
+            let mut x: [i8; 16] = [0; 16];
+            for (x, &(lo, hi)) in x.chunks_exact_mut(2).zip(chunk) {
+                x[0] = lo as i8;
+                x[1] = hi as i8;
+            }
+            let r = Vec16c::set_values(x);
+            m | unsafe { cmpestrm_sse42::<RANGES>(r, 2 * chunk.len(), b, lb) }
+        });
+        !inside & valid_bits(lb)
+    })
+}
+
+fn find_first_not_in_ranges_scalar(haystack: &[u8], ranges: &[(u8, u8)]) -> Option<usize> {
+    haystack
+        .iter()
+        .position(|&x| !ranges.iter().any(|&(lo, hi)| lo <= x && x <= hi))
+}
+
+/// Split haystack into the pieces separated by any byte of set, like
+/// `haystack.split(|x| set.contains(x))`
+pub fn split_on<'a, 's>(haystack: &'a [u8], set: &'s [u8]) -> SplitOn<'a, 's> {
+    SplitOn {
+        rest: Some(haystack),
+        set,
+    }
+}
+
+/// Iterator returned by split_on
+#[derive(Clone, Debug)]
+pub struct SplitOn<'a, 's> {
+    rest: Option<&'a [u8]>,
+    set: &'s [u8],
+}
+
+impl<'a> Iterator for SplitOn<'a, '_> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let rest = self.rest?;
+        match find_first_in_set(rest, self.set) {
+            Some(i) => {
+                self.rest = Some(&rest[i + 1..]);
+                Some(&rest[..i])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
+}
+
+/// Matches of the first lb elements of b with any byte of set, as bits
+#[inline]
+#[target_feature(enable = "sse4.2")]
+unsafe fn in_set(set: &[u8], b: Vec16c, lb: usize) -> u16 {
+    set.chunks(16).fold(0, |m, chunk| {
+        m | cmpestrm_sse42::<EQUAL_ANY>(load(chunk), chunk.len(), b, lb)
+    })
+}
+
+/// Load up to 16 bytes. The rest of the vector is zero
+#[inline]
+fn load(bytes: &[u8]) -> Vec16c {
+    let mut x: [u8; 16] = [0; 16];
+    x[..bytes.len()].copy_from_slice(bytes);
+    let mut v = Vec16c::new();
+    // SAFETY: x has 16 bytes
+    unsafe { v.load(&x as *const u8 as *const i8) };
+    v
+}
+
+/// Bits for the first n elements
+#[inline]
+fn valid_bits(n: usize) -> u16 {
+    (((1u32 << n) - 1) & 0xFFFF) as u16
+}
+
+/// Scan haystack 16 bytes at a time. f gets a block and its length and returns the matches as bits.
+/// Always inlined, so that f is compiled with SSE4.2 along with the caller
+#[inline(always)]
+fn find_by<F>(haystack: &[u8], f: F) -> Option<usize>
+where
+    F: Fn(Vec16c, usize) -> u16,
+{
+    let mut v = Vec16c::new();
+    let chunks = haystack.chunks_exact(16);
+    let rest = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        // SAFETY: each chunk is 16 bytes long
+        unsafe { v.load(chunk.as_ptr() as *const i8) };
+        let bits = f(v, 16);
+        if bits != 0 {
+            return Some(16 * i + bits.trailing_zeros() as usize);
+        }
+    }
+    if !rest.is_empty() {
+        let bits = f(load(rest), rest.len());
+        if bits != 0 {
+            return Some(haystack.len() - rest.len() + bits.trailing_zeros() as usize);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_string_instructions() {
+        if !has_sse42() {
+            return;
+        }
+        let a = load(b"aeiou");
+        let b = load(b"hello world");
+        assert_eq!(cmpistri::<EQUAL_ANY>(a, b), 1);
+        assert_eq!(cmpistri::<{ EQUAL_ANY | MOST_SIGNIFICANT }>(a, b), 7);
+        assert_eq!(cmpestri::<EQUAL_ANY>(a, 5, b, 1), 16);
+        assert_eq!(cmpestrm::<EQUAL_ANY>(a, 5, b, 11), 0b000_1001_0010);
+        assert_eq!(cmpestrm::<EQUAL_ANY>(a, 0, b, 11), 0);
+        assert_eq!(
+            cmpestrm::<{ EQUAL_ANY | MOST_SIGNIFICANT }>(a, 5, b, 11),
+            0b000_1001_0010
+        );
+
+        let r = load(b"az");
+        assert_eq!(cmpistri::<RANGES>(r, load(b"HELLO world")), 6);
+        assert_eq!(
+            cmpestri::<{ RANGES | MASKED_NEGATIVE_POLARITY }>(r, 2, b, 11),
+            5
+        );
+
+        assert_eq!(cmpistri::<EQUAL_EACH>(b, load(b"hello_world")), 0);
+        assert_eq!(
+            cmpistri::<{ EQUAL_EACH | NEGATIVE_POLARITY }>(b, load(b"hello_world")),
+            5
+        );
+        assert_eq!(cmpistri::<EQUAL_ORDERED>(load(b"wor"), b), 6);
+        assert_eq!(cmpistri::<EQUAL_ORDERED>(load(b"worm"), b), 16);
+
+        let s = load(&[0x80, 0x7F]);
+        let x = load(&[0x00, 0x90, 0x70]);
+        assert_eq!(cmpestrm::<RANGES>(s, 2, x, 3), 0b000);
+        assert_eq!(cmpestrm::<{ RANGES | SIGNED }>(s, 2, x, 3), 0b111);
+    }
+
+    #[test]
+    fn test_short_operands() {
+        // operands shorter than a vector, in allocations of their exact size, so that a read
+        // past the end shows up under AddressSanitizer or Miri
+        for len in 0..16 {
+            let haystack: Box<[u8]> = (0..len as u8).map(|x| b'a' + x).collect();
+            let set: Box<[u8]> = (0..len as u8).rev().map(|x| b'a' + x).collect();
+            assert_eq!(find_first_in_set(&haystack, &set), (len > 0).then_some(0));
+            let upper = &set[..len / 2];
+            assert_eq!(
+                find_first_in_set(&haystack, upper),
+                haystack.iter().position(|x| upper.contains(x))
+            );
+            assert_eq!(split_on(&haystack, &set).count(), len + 1);
+            assert_eq!(find_first_not_in_ranges(&haystack, &[(b'a', b'z')]), None);
+        }
+    }
+
+    #[test]
+    fn test_string_helpers() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..3000 {
+            let len = rng.gen_range(0..100);
+            let haystack: Vec<u8> = (0..len).map(|_| rng.gen_range(0..40)).collect();
+            let nset = rng.gen_range(0..20);
+            let set: Vec<u8> = (0..nset).map(|_| rng.gen_range(0..40)).collect();
+            assert_eq!(
+                find_first_in_set(&haystack, &set),
+                haystack.iter().position(|x| set.contains(x))
+            );
+
+            let split: Vec<&[u8]> = split_on(&haystack, &set).collect();
+            let expect: Vec<&[u8]> = haystack.split(|x| set.contains(x)).collect();
+            assert_eq!(split, expect);
+
+            let nranges = rng.gen_range(0..10);
+            let ranges: Vec<(u8, u8)> = (0..nranges)
+                .map(|_| {
+                    let lo = rng.gen_range(0..40);
+                    (lo, lo + rng.gen_range(0..5))
+                })
+                .collect();
+            assert_eq!(
+                find_first_not_in_ranges(&haystack, &ranges),
+                haystack
+                    .iter()
+                    .position(|&x| !ranges.iter().any(|&(lo, hi)| lo <= x && x <= hi))
+            );
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use crate::instrset::{instrset_detect, InstructionSet};
+use crate::vectori128::Vec16c;
+use core::arch::x86_64::*;
+use core::fmt;
+
+/// Error returned by validate_utf8. Same information as core::str::Utf8Error
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Utf8Error {
+    valid_up_to: usize,
+    error_len: Option<u8>,
+}
+
+impl Utf8Error {
+    /// Length of the longest prefix that is valid UTF-8
+    pub fn valid_up_to(&self) -> usize {
+        self.valid_up_to
+    }
+
+    /// Length of the invalid byte sequence at valid_up_to, or None if the input ends
+    /// in the middle of a sequence that could still become valid
+    pub fn error_len(&self) -> Option<usize> {
+        self.error_len.map(|len| len as usize)
+    }
+}
+
+impl From<core::str::Utf8Error> for Utf8Error {
+    fn from(e: core::str::Utf8Error) -> Self {
+        Utf8Error {
+            valid_up_to: e.valid_up_to(),
+            error_len: e.error_len().map(|len| len as u8),
+        }
+    }
+}
+
+impl fmt::Display for Utf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error_len {
+            Some(len) => write!(
+                f,
+                "invalid utf-8 sequence of {} bytes from index {}",
+                len, self.valid_up_to
+            ),
+            None => write!(
+                f,
+                "incomplete utf-8 byte sequence from index {}",
+                self.valid_up_to
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Utf8Error {}
+
+/// Returns true if all bytes are ASCII (below 0x80)
+pub fn is_ascii(input: &[u8]) -> bool {
+    unsafe {
+        let chunks = input.chunks_exact(16);
+        let rest = chunks.remainder();
+        let mut acc = _mm_setzero_si128();
+        for chunk in chunks {
+            acc = _mm_or_si128(acc, _mm_loadu_si128(chunk.as_ptr() as *const __m128i));
+        }
+        _mm_movemask_epi8(acc) == 0 && rest.iter().all(|&x| x < 0x80)
+    }
+}
+
+/// Check that input is valid UTF-8. On error, reports the position like core::str::from_utf8.
+///
+/// The vector algorithm needs SSSE3, which x86-64 does not have by default. It is compiled
+/// for SSSE3 and used when the build enables it or the CPU has it. Otherwise this is
+/// core::str::from_utf8
+pub fn validate_utf8(input: &[u8]) -> Result<(), Utf8Error> {
+    if cfg!(target_feature = "ssse3") || instrset_detect() >= InstructionSet::Ssse3 {
+        // SAFETY: the CPU has SSSE3
+        unsafe { validate_utf8_simd(input) }
+    } else {
+        validate_utf8_scalar(input, 0)
+    }
+}
+
+/// Validate input[start..], where start is at a character boundary of a valid prefix
+fn validate_utf8_scalar(input: &[u8], start: usize) -> Result<(), Utf8Error> {
+    match core::str::from_utf8(&input[start..]) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let mut e = Utf8Error::from(e);
+            e.valid_up_to += start;
+            Err(e)
+        }
+    }
+}
+
+/// Keiser-Lemire validation, 16 bytes at a time. When a block has an error, the scalar
+/// validator reruns from the last character boundary before it to find the exact position
+#[target_feature(enable = "ssse3")]
+unsafe fn validate_utf8_simd(input: &[u8]) -> Result<(), Utf8Error> {
+    let mut prev = Vec16c::new();
+    let blocks = input.chunks_exact(64);
+    let tail = blocks.remainder();
+    for (i, block) in blocks.enumerate() {
+        // ASCII fast path: 64 bytes without classification. Only a sequence left
+        // incomplete by the bytes before them can be an error
+        if ascii_block(block) {
+            if is_incomplete(prev) {
+                return validate_utf8_scalar(input, char_start(input, 64 * i));
+            }
+            prev = Vec16c::new();
+        } else if let Some(pos) = check_chunks(block, &mut prev) {
+            return validate_utf8_scalar(input, char_start(input, 64 * i + pos));
+        }
+    }
+    let start = input.len() - tail.len();
+    if let Some(pos) = check_chunks(tail, &mut prev) {
+        return validate_utf8_scalar(input, char_start(input, start + pos));
+    }
+    // the zero padding also catches a sequence left incomplete at the end of the input
+    let rest = &tail[tail.len() & !15..];
+    let mut x: [i8; 16] = [0; 16];
+    for (x, &c) in x.iter_mut().zip(rest) {
+        *x = c as i8;
+    }
+    if block_has_error(prev, Vec16c::set_values(x)) {
+        return validate_utf8_scalar(input, char_start(input, input.len() - rest.len()));
+    }
+    Ok(())
+}
+
+/// Check the whole 16-byte chunks of bytes, following prev. Returns the offset of the first
+/// chunk with an error
+#[inline]
+#[target_feature(enable = "ssse3")]
+unsafe fn check_chunks(bytes: &[u8], prev: &mut Vec16c) -> Option<usize> {
+    let mut cur = Vec16c::new();
+    for (i, chunk) in bytes.chunks_exact(16).enumerate() {
+        // SAFETY: each chunk is 16 bytes long
+        cur.load(chunk.as_ptr() as *const i8);
+        if block_has_error(*prev, cur) {
+            return Some(16 * i);
+        }
+        *prev = cur;
+    }
+    None
+}
+
+/// Returns true if all 64 bytes of block are ASCII
+#[inline]
+fn ascii_block(block: &[u8]) -> bool {
+    assert!(block.len() == 64);
+    unsafe {
+        let p = block.as_ptr() as *const __m128i;
+        let a = _mm_or_si128(_mm_loadu_si128(p), _mm_loadu_si128(p.add(1)));
+        let b = _mm_or_si128(_mm_loadu_si128(p.add(2)), _mm_loadu_si128(p.add(3)));
+        _mm_movemask_epi8(_mm_or_si128(a, b)) == 0
+    }
+}
+
+/// Returns true if the last bytes of v start a sequence that needs more bytes than are left
+#[inline]
+fn is_incomplete(v: Vec16c) -> bool {
+    unsafe {
+        // the last byte may be at most 0xBF, the one before 0xDF and the one before that 0xEF
+        let mut max = [-1i8; 16];
+        max[13] = 0xEFu8 as i8;
+        max[14] = 0xDFu8 as i8;
+        max[15] = 0xBFu8 as i8;
+        let over = _mm_subs_epu8(v.xmm, Vec16c::set_values(max).xmm);
+        _mm_movemask_epi8(_mm_cmpeq_epi8(over, _mm_setzero_si128())) != 0xFFFF
+    }
+}
+
+/// Start of the character containing input[pos], assuming input[..pos] was found valid
+/// apart from an incomplete last character
+fn char_start(input: &[u8], pos: usize) -> usize {
+    (pos.saturating_sub(3)..pos)
+        .rev()
+        .find(|&i| input[i] >= 0xC0)
+        .filter(|&i| input[i + 1..pos].iter().all(|&x| x & 0xC0 == 0x80))
+        .unwrap_or(pos)
+}
+
+// Error classes of a pair of consecutive bytes. Each table lookup gives the classes that are
+// possible for one nibble, and a pair is invalid if all three agree on some class
+mod class {
+    pub const TOO_SHORT: i8 = 1 << 0; // 11______ 0_______ or 11______ 11______
+    pub const TOO_LONG: i8 = 1 << 1; // 0_______ 10______
+    pub const OVERLONG_3: i8 = 1 << 2; // 11100000 100_____
+    pub const TOO_LARGE: i8 = 1 << 3; // 11110100 1001____ and above
+    pub const SURROGATE: i8 = 1 << 4; // 11101101 101_____
+    pub const OVERLONG_2: i8 = 1 << 5; // 1100000_ 10______
+    pub const TOO_LARGE_1000: i8 = 1 << 6; // 11110101 1000____ and above
+    pub const OVERLONG_4: i8 = 1 << 6; // 11110000 1000____
+    pub const TWO_CONTS: i8 = 1 << 7; // 10______ 10______
+    pub const CARRY: i8 = TOO_SHORT | TOO_LONG | TWO_CONTS;
+}
+
+/// lookup16 with pshufb. The vector class only uses pshufb when the whole build has SSSE3
+#[inline]
+#[target_feature(enable = "ssse3")]
+unsafe fn lookup(index: Vec16c, table: Vec16c) -> Vec16c {
+    let index = _mm_and_si128(index.xmm, _mm_set1_epi8(0x0F));
+    Vec16c {
+        xmm: _mm_shuffle_epi8(table.xmm, index),
+    }
+}
+
+/// Check the 16 bytes of cur, with prev being the 16 bytes before them
+#[inline]
+#[target_feature(enable = "ssse3")]
+unsafe fn block_has_error(prev: Vec16c, cur: Vec16c) -> bool {
+    use class::*;
+
+    // nothing to check if neither block has a byte above ASCII
+    if _mm_movemask_epi8(_mm_or_si128(prev.xmm, cur.xmm)) == 0 {
+        return false;
+    }
+
+    let prev1 = _mm_alignr_epi8::<15>(cur.xmm, prev.xmm);
+    let prev2 = _mm_alignr_epi8::<14>(cur.xmm, prev.xmm);
+    let prev3 = _mm_alignr_epi8::<13>(cur.xmm, prev.xmm);
+    let high = |v: __m128i| Vec16c {
+        xmm: _mm_srli_epi16(v, 4),
+    };
+
+    let byte_1_high = lookup(
+        high(prev1),
+        Vec16c::set_values([
+            // 0_______ ascii
+            TOO_LONG,
+            TOO_LONG,
+            TOO_LONG,
+            TOO_LONG,
+            TOO_LONG,
+            TOO_LONG,
+            TOO_LONG,
+            TOO_LONG,
+            // 10______ continuation
+            TWO_CONTS,
+            TWO_CONTS,
+            TWO_CONTS,
+            TWO_CONTS,
+            // 1100____ and 1101____ two-byte lead
+            TOO_SHORT | OVERLONG_2,
+            TOO_SHORT,
+            // 1110____ three-byte lead
+            TOO_SHORT | OVERLONG_3 | SURROGATE,
+            // 1111____ four-byte lead
+            TOO_SHORT | TOO_LARGE | TOO_LARGE_1000 | OVERLONG_4,
+        ]),
+    );
+    let byte_1_low = lookup(
+        Vec16c { xmm: prev1 },
+        Vec16c::set_values([
+            // ____0000
+            CARRY | OVERLONG_3 | OVERLONG_2 | OVERLONG_4,
+            // ____0001
+            CARRY | OVERLONG_2,
+            // ____001_
+            CARRY,
+            CARRY,
+            // ____0100
+            CARRY | TOO_LARGE,
+            // ____0101 and above
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            // ____1101
+            CARRY | TOO_LARGE | TOO_LARGE_1000 | SURROGATE,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+            CARRY | TOO_LARGE | TOO_LARGE_1000,
+        ]),
+    );
+    let byte_2_high = lookup(
+        high(cur.xmm),
+        Vec16c::set_values([
+            // 0_______ ascii
+            TOO_SHORT,
+            TOO_SHORT,
+            TOO_SHORT,
+            TOO_SHORT,
+            TOO_SHORT,
+            TOO_SHORT,
+            TOO_SHORT,
+            TOO_SHORT,
+            // 1000____
+            TOO_LONG | OVERLONG_2 | TWO_CONTS | OVERLONG_3 | TOO_LARGE_1000 | OVERLONG_4,
+            // 1001____
+            TOO_LONG | OVERLONG_2 | TWO_CONTS | OVERLONG_3 | TOO_LARGE,
+            // 101_____
+            TOO_LONG | OVERLONG_2 | TWO_CONTS | SURROGATE | TOO_LARGE,
+            TOO_LONG | OVERLONG_2 | TWO_CONTS | SURROGATE | TOO_LARGE,
+            // 11______ lead
+            TOO_SHORT,
+            TOO_SHORT,
+            TOO_SHORT,
+            TOO_SHORT,
+        ]),
+    );
+    let special = _mm_and_si128(
+        _mm_and_si128(byte_1_high.xmm, byte_1_low.xmm),
+        byte_2_high.xmm,
+    );
+
+    // the second byte after a three- or four-byte lead and the third after a four-byte lead
+    // must be continuations. These are the TWO_CONTS cases that are not errors
+    let third = _mm_subs_epu8(prev2, _mm_set1_epi8((0xE0u8 - 0x80) as i8));
+    let fourth = _mm_subs_epu8(prev3, _mm_set1_epi8((0xF0u8 - 0x80) as i8));
+    let must23 = _mm_and_si128(_mm_or_si128(third, fourth), _mm_set1_epi8(i8::MIN));
+    let error = _mm_xor_si128(must23, special);
+
+    _mm_movemask_epi8(_mm_cmpeq_epi8(error, _mm_setzero_si128())) != 0xFFFF
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn check(input: &[u8]) {
+        let expect = core::str::from_utf8(input)
+            .map(|_| ())
+            .map_err(Utf8Error::from);
+        assert_eq!(validate_utf8(input), expect, "{:x?}", input);
+        assert_eq!(is_ascii(input), input.is_ascii(), "{:x?}", input);
+    }
+
+    #[test]
+    fn test_utf8_exhaustive() {
+        // every sequence of up to 2 bytes
+        check(&[]);
+        for a in 0..=255u8 {
+            check(&[a]);
+            for b in 0..=255u8 {
+                check(&[a, b]);
+            }
+        }
+
+        // up to 4 bytes from the boundary values of every byte class, placed so that they
+        // cross from one block into the next or end a block followed by 64 ASCII bytes
+        let edges: [u8; 24] = [
+            0x00, 0x7F, 0x80, 0x8F, 0x90, 0x9F, 0xA0, 0xBF, 0xC0, 0xC1, 0xC2, 0xDF, 0xE0, 0xE1,
+            0xEC, 0xED, 0xEE, 0xEF, 0xF0, 0xF1, 0xF3, 0xF4, 0xF5, 0xFF,
+        ];
+        let mut input = [b'a'; 144];
+        for &a in edges.iter() {
+            for &b in edges.iter() {
+                for &c in edges.iter() {
+                    check(&[a, b, c]);
+                    for &d in edges.iter() {
+                        check(&[a, b, c, d]);
+                        for offset in [13, 15, 60, 63] {
+                            input[offset..offset + 4].copy_from_slice(&[a, b, c, d]);
+                            check(&input[..offset + 4]);
+                            check(&input);
+                            input[offset..offset + 4].copy_from_slice(b"aaaa");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_utf8_random() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..3000 {
+            // valid text of mixed character lengths, then a few random byte changes
+            let len = rng.gen_range(0..100);
+            let text: String = (0..len)
+                .map(|_| match rng.gen_range(0..4) {
+                    0 => rng.gen_range('\u{0}'..='\u{7F}'),
+                    1 => rng.gen_range('\u{80}'..='\u{7FF}'),
+                    2 => rng.gen_range('\u{800}'..='\u{FFFF}'),
+                    _ => rng.gen_range('\u{10000}'..='\u{10FFFF}'),
+                })
+                .collect();
+            let mut bytes = text.into_bytes();
+            // runs of ASCII long enough for the ASCII fast path
+            if rng.gen() {
+                let at = rng.gen_range(0..=bytes.len());
+                let run = rng.gen_range(0..200);
+                bytes.splice(at..at, (0..run).map(|_| rng.gen_range(0..0x80)));
+            }
+            check(&bytes);
+            for _ in 0..3 {
+                if bytes.is_empty() {
+                    break;
+                }
+                let i = rng.gen_range(0..bytes.len());
+                bytes[i] = rng.gen();
+                check(&bytes);
+                check(&bytes[..i]);
+            }
+        }
+    }
+}
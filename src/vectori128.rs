@@ -1,7 +1,8 @@
-use std::arch::x86_64::*;
-use std::ops;
+use core::arch::x86_64::*;
+use core::hash::{Hash, Hasher};
+use core::ops;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone)]
 pub struct Vec128b {
     xmm: __m128i,
 }
@@ -9,6 +10,19 @@ pub struct Vec128b {
 impl Vec128b {
     pub const LEN: usize = 128;
 
+    /// All bits zero
+    pub const ZERO: Self = Self::from_array_const([0; 16]);
+
+    /// All bits one
+    pub const ONES: Self = Self::from_array_const([0xFF; 16]);
+
+    /// Constructor from an array of bytes that can be used in constant expressions
+    pub const fn from_array_const(x: [u8; 16]) -> Self {
+        Vec128b {
+            xmm: unsafe { core::mem::transmute::<[u8; 16], __m128i>(x) },
+        }
+    }
+
     pub fn new() -> Self {
         Vec128b {
             xmm: unsafe { _mm_setzero_si128() },
@@ -46,6 +60,31 @@ impl Vec128b {
     pub unsafe fn store_aligned(&self, mem_addr: *mut u8) {
         _mm_store_si128(mem_addr as *mut __m128i, self.xmm);
     }
+
+    /// Shift all bytes up by N positions, zero-filling the lowest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_up<const N: i32>(self) -> Self {
+        Vec128b {
+            xmm: shift_bytes_up_si128::<N>(self.xmm),
+        }
+    }
+
+    /// Shift all bytes down by N positions, zero-filling the highest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_down<const N: i32>(self) -> Self {
+        Vec128b {
+            xmm: shift_bytes_down_si128::<N>(self.xmm),
+        }
+    }
+
+    /// Extract 16 consecutive bytes starting at byte N from the 32-byte concatenation hi:lo,
+    /// lo being the lower half. N must be in 0..=16
+    #[inline]
+    pub fn align_right<const N: i32>(hi: Self, lo: Self) -> Self {
+        Vec128b {
+            xmm: align_right_si128::<N>(hi.xmm, lo.xmm),
+        }
+    }
 }
 
 impl Default for Vec128b {
@@ -54,6 +93,20 @@ impl Default for Vec128b {
     }
 }
 
+impl PartialEq for Vec128b {
+    fn eq(&self, other: &Self) -> bool {
+        Vec16c::from(*self) == Vec16c::from(*other)
+    }
+}
+
+impl Eq for Vec128b {}
+
+impl Hash for Vec128b {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Vec16c::from(*self).hash(state)
+    }
+}
+
 impl ops::BitAnd for Vec128b {
     type Output = Self;
 
@@ -142,6 +195,39 @@ pub(crate) unsafe fn selectb(s: __m128i, a: __m128i, b: __m128i) -> __m128i {
     unsafe { _mm_or_si128(_mm_and_si128(s, a), _mm_andnot_si128(s, b)) }
 }
 
+/// Shift the whole register up by N bytes (towards higher element index), zero-filling the low bytes
+#[inline]
+fn shift_bytes_up_si128<const N: i32>(a: __m128i) -> __m128i {
+    const { assert!(N >= 0 && N <= 16, "byte shift count must be in 0..=16") };
+    unsafe { _mm_slli_si128::<N>(a) }
+}
+
+/// Shift the whole register down by N bytes (towards lower element index), zero-filling the high bytes
+#[inline]
+fn shift_bytes_down_si128<const N: i32>(a: __m128i) -> __m128i {
+    const { assert!(N >= 0 && N <= 16, "byte shift count must be in 0..=16") };
+    unsafe { _mm_srli_si128::<N>(a) }
+}
+
+/// Concatenate hi:lo into 32 bytes and extract the 16 bytes starting at byte N (palignr).
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 16; i++) result[i] = i + N < 16 ? lo[i + N] : hi[i + N - 16];
+#[inline]
+fn align_right_si128<const N: i32>(hi: __m128i, lo: __m128i) -> __m128i {
+    const { assert!(N >= 0 && N <= 16, "alignment must be in 0..=16") };
+    #[cfg(target_feature = "ssse3")]
+    unsafe {
+        _mm_alignr_epi8::<N>(hi, lo)
+    }
+    #[cfg(not(target_feature = "ssse3"))]
+    unsafe {
+        let mut x: [u8; 32] = [0; 32];
+        _mm_storeu_si128(&mut x as *mut u8 as *mut __m128i, lo);
+        _mm_storeu_si128((&mut x as *mut u8).add(16) as *mut __m128i, hi);
+        _mm_loadu_si128((&x as *const u8).add(N as usize) as *const __m128i)
+    }
+}
+
 ///# Safety: obvious
 ///
 /// Returns false if at least one bit is 0
@@ -166,14 +252,27 @@ pub fn horizontal_or(a: Vec128b) -> bool {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone)]
 pub struct Vec16c {
-    xmm: __m128i,
+    pub(crate) xmm: __m128i,
 }
 
 impl Vec16c {
     pub const LEN: usize = 16;
 
+    /// All elements zero
+    pub const ZERO: Self = Self::from_array_const([0; 16]);
+
+    /// All bits one, i.e. all elements -1
+    pub const ONES: Self = Self::from_array_const([-1; 16]);
+
+    /// Constructor from an array that can be used in constant expressions
+    pub const fn from_array_const(x: [i8; 16]) -> Self {
+        Vec16c {
+            xmm: unsafe { core::mem::transmute::<[i8; 16], __m128i>(x) },
+        }
+    }
+
     pub fn new() -> Self {
         Vec16c {
             xmm: unsafe { _mm_setzero_si128() },
@@ -306,14 +405,64 @@ impl Vec16c {
         self.store(&mut x as *mut i8);
         x[index & 0x0F]
     }
+
+    /// Shift all bytes up by N positions, zero-filling the lowest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_up<const N: i32>(self) -> Self {
+        Vec16c {
+            xmm: shift_bytes_up_si128::<N>(self.xmm),
+        }
+    }
+
+    /// Shift all bytes down by N positions, zero-filling the highest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_down<const N: i32>(self) -> Self {
+        Vec16c {
+            xmm: shift_bytes_down_si128::<N>(self.xmm),
+        }
+    }
+
+    /// Extract 16 consecutive bytes starting at byte N from the 32-byte concatenation hi:lo,
+    /// lo being the lower half. N must be in 0..=16
+    #[inline]
+    pub fn align_right<const N: i32>(hi: Self, lo: Self) -> Self {
+        Vec16c {
+            xmm: align_right_si128::<N>(hi.xmm, lo.xmm),
+        }
+    }
+}
+
+impl Default for Vec16c {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone)]
 pub struct Vec16bc {
     xmm: __m128i,
 }
 
 impl Vec16bc {
+    /// All elements false
+    pub const ZERO: Self = Self::from_array_const([false; 16]);
+
+    /// All elements true
+    pub const ONES: Self = Self::from_array_const([true; 16]);
+
+    /// Constructor from an array that can be used in constant expressions
+    pub const fn from_array_const(x: [bool; 16]) -> Self {
+        let mut y = [0i8; 16];
+        let mut i = 0;
+        while i < 16 {
+            y[i] = -(x[i] as i8);
+            i += 1;
+        }
+        Vec16bc {
+            xmm: unsafe { core::mem::transmute::<[i8; 16], __m128i>(y) },
+        }
+    }
+
     pub fn set_values(
         x0: bool,
         x1: bool,
@@ -360,6 +509,97 @@ impl Vec16bc {
     }
 }
 
+impl Default for Vec16bc {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl PartialEq for Vec16bc {
+    fn eq(&self, other: &Self) -> bool {
+        Vec16c::from(*self) == Vec16c::from(*other)
+    }
+}
+
+impl Eq for Vec16bc {}
+
+impl Hash for Vec16bc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Vec16c::from(*self).hash(state)
+    }
+}
+
+/// Get the bits of a boolean vector as an integer, bit i corresponding to element i
+#[inline]
+pub fn to_bits(x: Vec16bc) -> u16 {
+    unsafe { _mm_movemask_epi8(x.xmm) as u16 }
+}
+
+/// Horizontal find first: index of the first true element, or None if all are false
+#[inline]
+pub fn horizontal_find_first(x: Vec16bc) -> Option<usize> {
+    let bits = to_bits(x);
+    if bits == 0 {
+        None
+    } else {
+        Some(bits.trailing_zeros() as usize)
+    }
+}
+
+/// Horizontal count: number of true elements
+#[inline]
+pub fn horizontal_count(x: Vec16bc) -> u32 {
+    to_bits(x).count_ones()
+}
+
+/// vector operator & : and of boolean vectors
+impl ops::BitAnd for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, other: Self) -> Self {
+        Vec16bc {
+            xmm: unsafe { _mm_and_si128(self.xmm, other.xmm) },
+        }
+    }
+}
+
+/// vector operator | : or of boolean vectors
+impl ops::BitOr for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, other: Self) -> Self {
+        Vec16bc {
+            xmm: unsafe { _mm_or_si128(self.xmm, other.xmm) },
+        }
+    }
+}
+
+/// vector operator ^ : xor of boolean vectors
+impl ops::BitXor for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitxor(self, other: Self) -> Self {
+        Vec16bc {
+            xmm: unsafe { _mm_xor_si128(self.xmm, other.xmm) },
+        }
+    }
+}
+
+/// vector operator ! : not of a boolean vector
+impl ops::Not for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Self {
+        Vec16bc {
+            xmm: unsafe { _mm_xor_si128(self.xmm, _mm_set1_epi32(-1)) },
+        }
+    }
+}
+
 /*****************************************************************************
 *
 *          Define operators for Vec16c
@@ -373,6 +613,126 @@ impl From<Vec16bc> for Vec16c {
     }
 }
 
+/*****************************************************************************
+*
+*          Conversions to and from the raw register type
+*
+*****************************************************************************/
+
+// The vectors are stored in the register in little endian order: element i of Vec16c and
+// Vec16bc is byte i of the __m128i, as with _mm_loadu_si128 from an array. Conversions between
+// vectors of the same size keep all the bits and compile to nothing.
+// There are no Vec4i, Vec4f or 256 and 512-bit vector classes yet. Vec128b converts to and from
+// __m128 and __m128d, so that the same bits can be used as floats with the intrinsics
+
+/// Wrap a raw register
+impl From<__m128i> for Vec128b {
+    #[inline]
+    fn from(x: __m128i) -> Self {
+        Vec128b { xmm: x }
+    }
+}
+
+/// Get the raw register
+impl From<Vec128b> for __m128i {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        a.xmm
+    }
+}
+
+/// Wrap a raw register, element i being byte i
+impl From<__m128i> for Vec16c {
+    #[inline]
+    fn from(x: __m128i) -> Self {
+        Vec16c { xmm: x }
+    }
+}
+
+/// Get the raw register, element i being byte i
+impl From<Vec16c> for __m128i {
+    #[inline]
+    fn from(a: Vec16c) -> Self {
+        a.xmm
+    }
+}
+
+/// Wrap a raw register. Each byte must be either 0 (false) or -1 (true), as for set_value
+impl From<__m128i> for Vec16bc {
+    #[inline]
+    fn from(x: __m128i) -> Self {
+        Vec16bc { xmm: x }
+    }
+}
+
+/// Get the raw register, with 0 (false) or -1 (true) in byte i for element i
+impl From<Vec16bc> for __m128i {
+    #[inline]
+    fn from(a: Vec16bc) -> Self {
+        a.xmm
+    }
+}
+
+/// Reinterpret the 128 bits as 16 elements of 8 bits
+impl From<Vec128b> for Vec16c {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        Vec16c { xmm: a.xmm }
+    }
+}
+
+/// Reinterpret the 16 elements as 128 bits
+impl From<Vec16c> for Vec128b {
+    #[inline]
+    fn from(a: Vec16c) -> Self {
+        Vec128b { xmm: a.xmm }
+    }
+}
+
+/// Reinterpret the 16 boolean elements as 128 bits
+impl From<Vec16bc> for Vec128b {
+    #[inline]
+    fn from(a: Vec16bc) -> Self {
+        Vec128b { xmm: a.xmm }
+    }
+}
+
+/// Reinterpret 4 floats as 128 bits, bytes 4i to 4i+3 being float i
+impl From<__m128> for Vec128b {
+    #[inline]
+    fn from(x: __m128) -> Self {
+        Vec128b {
+            xmm: unsafe { _mm_castps_si128(x) },
+        }
+    }
+}
+
+/// Reinterpret the 128 bits as 4 floats, bytes 4i to 4i+3 being float i
+impl From<Vec128b> for __m128 {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        unsafe { _mm_castsi128_ps(a.xmm) }
+    }
+}
+
+/// Reinterpret 2 doubles as 128 bits, bytes 8i to 8i+7 being double i
+impl From<__m128d> for Vec128b {
+    #[inline]
+    fn from(x: __m128d) -> Self {
+        Vec128b {
+            xmm: unsafe { _mm_castpd_si128(x) },
+        }
+    }
+}
+
+/// Reinterpret the 128 bits as 2 doubles, bytes 8i to 8i+7 being double i
+impl From<Vec128b> for __m128d {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        unsafe { _mm_castsi128_pd(a.xmm) }
+    }
+}
+
 /// vector operator + : add element by element
 impl ops::Add for Vec16c {
     type Output = Self;
@@ -508,7 +868,7 @@ impl ops::BitAnd for Vec16c {
     #[inline]
     fn bitand(self, other: Self) -> Self {
         Self {
-            xmm: unsafe { _mm_add_epi8(self.xmm, other.xmm) },
+            xmm: unsafe { _mm_and_si128(self.xmm, other.xmm) },
         }
     }
 }
@@ -517,17 +877,55 @@ impl ops::BitAnd for Vec16c {
 impl ops::BitAndAssign for Vec16c {
     #[inline]
     fn bitand_assign(&mut self, other: Self) {
-        *self = *self - other
+        *self = *self & other
     }
 }
 
 /// vector operator == : equality
 impl PartialEq for Vec16c {
     fn eq(&self, other: &Self) -> bool {
+        #[cfg(target_feature = "sse4.1")]
         unsafe {
             let neq = _mm_xor_si128(self.xmm, other.xmm);
             _mm_test_all_zeros(neq, neq) > 0
         }
+        #[cfg(not(target_feature = "sse4.1"))]
+        unsafe {
+            _mm_movemask_epi8(_mm_cmpeq_epi8(self.xmm, other.xmm)) == 0xFFFF
+        }
+    }
+}
+
+impl Eq for Vec16c {}
+
+/// Hash of the elements, in agreement with ==
+impl Hash for Vec16c {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_array().hash(state)
+    }
+}
+
+/// function compare_eq: a[i] == b[i] for each element
+#[inline]
+pub fn compare_eq(a: Vec16c, b: Vec16c) -> Vec16bc {
+    Vec16bc {
+        xmm: unsafe { _mm_cmpeq_epi8(a.xmm, b.xmm) },
+    }
+}
+
+/// function compare_gt: a[i] > b[i] for each element, signed
+#[inline]
+pub fn compare_gt(a: Vec16c, b: Vec16c) -> Vec16bc {
+    Vec16bc {
+        xmm: unsafe { _mm_cmpgt_epi8(a.xmm, b.xmm) },
+    }
+}
+
+/// function compare_lt: a[i] < b[i] for each element, signed
+#[inline]
+pub fn compare_lt(a: Vec16c, b: Vec16c) -> Vec16bc {
+    Vec16bc {
+        xmm: unsafe { _mm_cmplt_epi8(a.xmm, b.xmm) },
     }
 }
 
@@ -569,7 +967,7 @@ pub fn horizontal_add(a: Vec16c) -> i32 {
         let sum1 = _mm_sad_epu8(a.xmm, _mm_setzero_si128());
         let sum2 = _mm_unpackhi_epi64(sum1, sum1);
         let sum3 = _mm_add_epi16(sum1, sum2);
-        _mm_cvtsi128_si32(sum3) as i32
+        _mm_cvtsi128_si32(sum3) as i8 as i32
     }
 }
 
@@ -585,7 +983,47 @@ pub fn horizontal_add_x(a: Vec16c) -> i32 {
         let sum2 = _mm_add_epi16(sum1, _mm_unpackhi_epi64(sum1, sum1));
         let sum3 = _mm_add_epi16(sum2, _mm_shuffle_epi32(sum2, 1));
         let sum4 = _mm_add_epi16(sum3, _mm_shufflelo_epi16(sum3, 1));
-        _mm_cvtsi128_si32(sum4) as i32
+        _mm_cvtsi128_si32(sum4) as i16 as i32
+    }
+}
+
+/// Horizontal min: Smallest element of the vector
+#[inline]
+pub fn horizontal_min(a: Vec16c) -> i8 {
+    unsafe {
+        // flip the sign bit so that unsigned order equals signed order
+        let signbit = _mm_set1_epi32(0x80808080u32 as i32);
+        horizontal_min_epu8(_mm_xor_si128(a.xmm, signbit)) as i8 ^ i8::MIN
+    }
+}
+
+/// Horizontal max: Largest element of the vector
+#[inline]
+pub fn horizontal_max(a: Vec16c) -> i8 {
+    unsafe {
+        // flip all bits but the sign bit so that the largest element becomes the smallest unsigned
+        let flip = _mm_set1_epi32(0x7F7F7F7F);
+        horizontal_min_epu8(_mm_xor_si128(a.xmm, flip)) as i8 ^ i8::MAX
+    }
+}
+
+/// Smallest unsigned byte of x
+#[inline]
+fn horizontal_min_epu8(x: __m128i) -> u8 {
+    #[cfg(target_feature = "sse4.1")]
+    unsafe {
+        // min of each byte pair zero-extended to 16 bits, then phminposuw
+        let m = _mm_min_epu8(x, _mm_srli_epi16(x, 8));
+        let m = _mm_and_si128(m, _mm_set1_epi16(0x00FF));
+        _mm_cvtsi128_si32(_mm_minpos_epu16(m)) as u8
+    }
+    #[cfg(not(target_feature = "sse4.1"))]
+    unsafe {
+        let m = _mm_min_epu8(x, _mm_unpackhi_epi64(x, x));
+        let m = _mm_min_epu8(m, _mm_srli_epi64(m, 32));
+        let m = _mm_min_epu8(m, _mm_srli_epi32(m, 16));
+        let m = _mm_min_epu8(m, _mm_srli_epi16(m, 8));
+        _mm_cvtsi128_si32(m) as u8
     }
 }
 
@@ -660,6 +1098,254 @@ pub fn abs_saturated(a: Vec16c) -> Vec16c {
     }
 }
 
+/// function avg: (a + b + 1) >> 1, computed without overflow
+#[inline]
+pub fn avg(a: Vec16c, b: Vec16c) -> Vec16c {
+    unsafe {
+        // pavgb is unsigned. Flip the sign bit to map signed to unsigned order and back
+        let signbit = _mm_set1_epi32(0x80808080u32 as i32);
+        let a1 = _mm_xor_si128(a.xmm, signbit);
+        let b1 = _mm_xor_si128(b.xmm, signbit);
+        Vec16c {
+            xmm: _mm_xor_si128(_mm_avg_epu8(a1, b1), signbit),
+        }
+    }
+}
+
+/// function abs_diff: |a - b| computed without overflow.
+/// The result is in 0..=255, so it is returned as unsigned bytes in Vec128b, as from abs_diff_u8
+#[inline]
+pub fn abs_diff(a: Vec16c, b: Vec16c) -> Vec128b {
+    unsafe {
+        let signbit = _mm_set1_epi32(0x80808080u32 as i32);
+        let a1 = _mm_xor_si128(a.xmm, signbit);
+        let b1 = _mm_xor_si128(b.xmm, signbit);
+        Vec128b {
+            xmm: _mm_or_si128(_mm_subs_epu8(a1, b1), _mm_subs_epu8(b1, a1)),
+        }
+    }
+}
+
+/// function sad: sum of absolute differences.
+/// Returns the sum of |a[i] - b[i]| over elements 0..8 and over elements 8..16
+#[inline]
+pub fn sad(a: Vec16c, b: Vec16c) -> [u64; 2] {
+    unsafe {
+        // the absolute difference is the same after flipping the sign bit of both operands
+        let signbit = _mm_set1_epi32(0x80808080u32 as i32);
+        let a1 = _mm_xor_si128(a.xmm, signbit);
+        let b1 = _mm_xor_si128(b.xmm, signbit);
+        let sum = _mm_sad_epu8(a1, b1);
+        [
+            _mm_cvtsi128_si64(sum) as u64,
+            _mm_cvtsi128_si64(_mm_unpackhi_epi64(sum, sum)) as u64,
+        ]
+    }
+}
+
+/// function mpsad: multiple sums of absolute differences (mpsadbw), for block matching.
+/// Compares the 4-element block of b starting at 4 * (IMM8 & 3) with the eight 4-element windows
+/// of a starting at elements 4 * ((IMM8 >> 2) & 1) + i. Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = sum(j = 0..4, |a[aoff + i + j] - b[boff + j]|);
+#[inline]
+pub fn mpsad<const IMM8: i32>(a: Vec16c, b: Vec16c) -> [u16; 8] {
+    const {
+        assert!(
+            IMM8 >= 0 && IMM8 <= 7,
+            "mpsad offset selector must be in 0..=7"
+        )
+    };
+    #[cfg(target_feature = "sse4.1")]
+    unsafe {
+        let signbit = _mm_set1_epi32(0x80808080u32 as i32);
+        let a1 = _mm_xor_si128(a.xmm, signbit);
+        let b1 = _mm_xor_si128(b.xmm, signbit);
+        let mut x: [u16; 8] = [0; 8];
+        _mm_storeu_si128(
+            &mut x as *mut u16 as *mut __m128i,
+            _mm_mpsadbw_epu8::<IMM8>(a1, b1),
+        );
+        x
+    }
+    #[cfg(not(target_feature = "sse4.1"))]
+    unsafe {
+        let mut xa: [i8; 16] = [0; 16];
+        let mut xb: [i8; 16] = [0; 16];
+        a.store(&mut xa as *mut i8);
+        b.store(&mut xb as *mut i8);
+        let aoff = 4 * ((IMM8 as usize >> 2) & 1);
+        let boff = 4 * (IMM8 as usize & 3);
+        core::array::from_fn(|i| {
+            (0..4)
+                .map(|j| xa[aoff + i + j].abs_diff(xb[boff + j]) as u16)
+                .sum()
+        })
+    }
+}
+
+// Unsigned versions for u8 data such as pixels. The elements of the Vec128b operands are read
+// as u8, as by Vec128b::to_array
+
+/// function avg_u8: (a + b + 1) >> 1 for unsigned elements, computed without overflow
+#[inline]
+pub fn avg_u8(a: Vec128b, b: Vec128b) -> Vec128b {
+    Vec128b {
+        xmm: unsafe { _mm_avg_epu8(a.xmm, b.xmm) },
+    }
+}
+
+/// function abs_diff_u8: |a - b| for unsigned elements
+#[inline]
+pub fn abs_diff_u8(a: Vec128b, b: Vec128b) -> Vec128b {
+    Vec128b {
+        xmm: unsafe { _mm_or_si128(_mm_subs_epu8(a.xmm, b.xmm), _mm_subs_epu8(b.xmm, a.xmm)) },
+    }
+}
+
+/// function sad_u8: sum of absolute differences of unsigned elements.
+/// Returns the sum of |a[i] - b[i]| over elements 0..8 and over elements 8..16
+#[inline]
+pub fn sad_u8(a: Vec128b, b: Vec128b) -> [u64; 2] {
+    unsafe {
+        let sum = _mm_sad_epu8(a.xmm, b.xmm);
+        [
+            _mm_cvtsi128_si64(sum) as u64,
+            _mm_cvtsi128_si64(_mm_unpackhi_epi64(sum, sum)) as u64,
+        ]
+    }
+}
+
+/// function mpsad_u8: multiple sums of absolute differences of unsigned elements (mpsadbw).
+/// The blocks are selected by IMM8 as in mpsad
+#[inline]
+pub fn mpsad_u8<const IMM8: i32>(a: Vec128b, b: Vec128b) -> [u16; 8] {
+    const {
+        assert!(
+            IMM8 >= 0 && IMM8 <= 7,
+            "mpsad offset selector must be in 0..=7"
+        )
+    };
+    #[cfg(target_feature = "sse4.1")]
+    unsafe {
+        let mut x: [u16; 8] = [0; 8];
+        _mm_storeu_si128(
+            &mut x as *mut u16 as *mut __m128i,
+            _mm_mpsadbw_epu8::<IMM8>(a.xmm, b.xmm),
+        );
+        x
+    }
+    #[cfg(not(target_feature = "sse4.1"))]
+    {
+        let (xa, xb) = (a.to_array(), b.to_array());
+        let aoff = 4 * ((IMM8 as usize >> 2) & 1);
+        let boff = 4 * (IMM8 as usize & 3);
+        core::array::from_fn(|i| {
+            (0..4)
+                .map(|j| xa[aoff + i + j].abs_diff(xb[boff + j]) as u16)
+                .sum()
+        })
+    }
+}
+
+/// function mul_hi: high 8 bits of the 16-bit product of each pair of elements, (a * b) >> 8
+#[inline]
+pub fn mul_hi(a: Vec16c, b: Vec16c) -> Vec16c {
+    unsafe {
+        // sign-extend even and odd elements to 16 bits and multiply
+        let aeven = _mm_srai_epi16(_mm_slli_epi16(a.xmm, 8), 8);
+        let beven = _mm_srai_epi16(_mm_slli_epi16(b.xmm, 8), 8);
+        let aodd = _mm_srai_epi16(a.xmm, 8);
+        let bodd = _mm_srai_epi16(b.xmm, 8);
+        let muleven = _mm_srli_epi16(_mm_mullo_epi16(aeven, beven), 8);
+        let mulodd = _mm_mullo_epi16(aodd, bodd);
+        let mask = _mm_set1_epi32(0x00FF00FF);
+        Vec16c {
+            xmm: selectb(mask, muleven, mulodd),
+        }
+    }
+}
+
+/// function mul_wide: full 16-bit products of all elements.
+/// Returns the products of elements 0..8 and of elements 8..16
+#[inline]
+pub fn mul_wide(a: Vec16c, b: Vec16c) -> ([i16; 8], [i16; 8]) {
+    unsafe {
+        // unpack each byte into the high half of a 16-bit element, then shift down with sign
+        let alo = _mm_srai_epi16(_mm_unpacklo_epi8(a.xmm, a.xmm), 8);
+        let blo = _mm_srai_epi16(_mm_unpacklo_epi8(b.xmm, b.xmm), 8);
+        let ahi = _mm_srai_epi16(_mm_unpackhi_epi8(a.xmm, a.xmm), 8);
+        let bhi = _mm_srai_epi16(_mm_unpackhi_epi8(b.xmm, b.xmm), 8);
+        let mut lo: [i16; 8] = [0; 8];
+        let mut hi: [i16; 8] = [0; 8];
+        _mm_storeu_si128(
+            &mut lo as *mut i16 as *mut __m128i,
+            _mm_mullo_epi16(alo, blo),
+        );
+        _mm_storeu_si128(
+            &mut hi as *mut i16 as *mut __m128i,
+            _mm_mullo_epi16(ahi, bhi),
+        );
+        (lo, hi)
+    }
+}
+
+/// function madd_u8i8: products of the unsigned elements of a and the signed elements of b,
+/// added in pairs with signed saturation (pmaddubsw). Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = saturate_i16(a[2i] * b[2i] + a[2i+1] * b[2i+1]);
+#[inline]
+pub fn madd_u8i8(a: Vec128b, b: Vec16c) -> [i16; 8] {
+    unsafe {
+        #[cfg(target_feature = "ssse3")]
+        let sum = _mm_maddubs_epi16(a.xmm, b.xmm);
+        // the pairwise sums fit in i32, so they can be saturated afterwards
+        #[cfg(not(target_feature = "ssse3"))]
+        let sum = {
+            let zero = _mm_setzero_si128();
+            let alo = _mm_unpacklo_epi8(a.xmm, zero);
+            let blo = _mm_srai_epi16(_mm_unpacklo_epi8(b.xmm, b.xmm), 8);
+            let ahi = _mm_unpackhi_epi8(a.xmm, zero);
+            let bhi = _mm_srai_epi16(_mm_unpackhi_epi8(b.xmm, b.xmm), 8);
+            _mm_packs_epi32(_mm_madd_epi16(alo, blo), _mm_madd_epi16(ahi, bhi))
+        };
+        let mut x: [i16; 8] = [0; 8];
+        _mm_storeu_si128(&mut x as *mut i16 as *mut __m128i, sum);
+        x
+    }
+}
+
+/// function madd_i16: products of signed 16-bit elements added in pairs (pmaddwd).
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 4; i++) result[i] = a[2i] * b[2i] + a[2i+1] * b[2i+1];
+/// Only -32768 * -32768 + -32768 * -32768 overflows, and wraps around to i32::MIN
+#[inline]
+pub fn madd_i16(a: [i16; 8], b: [i16; 8]) -> [i32; 4] {
+    unsafe {
+        let va = _mm_loadu_si128(&a as *const i16 as *const __m128i);
+        let vb = _mm_loadu_si128(&b as *const i16 as *const __m128i);
+        let mut x: [i32; 4] = [0; 4];
+        _mm_storeu_si128(&mut x as *mut i32 as *mut __m128i, _mm_madd_epi16(va, vb));
+        x
+    }
+}
+
+/// function mul_hrs: high half of the product of signed 16-bit elements in fixed point, rounded
+/// (pmulhrsw). Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = (a[i] * b[i] + 0x4000) >> 15;
+/// Only -32768 * -32768 overflows, and wraps around to -32768
+#[inline]
+pub fn mul_hrs(a: [i16; 8], b: [i16; 8]) -> [i16; 8] {
+    #[cfg(target_feature = "ssse3")]
+    unsafe {
+        let va = _mm_loadu_si128(&a as *const i16 as *const __m128i);
+        let vb = _mm_loadu_si128(&b as *const i16 as *const __m128i);
+        let mut x: [i16; 8] = [0; 8];
+        _mm_storeu_si128(&mut x as *mut i16 as *mut __m128i, _mm_mulhrs_epi16(va, vb));
+        x
+    }
+    #[cfg(not(target_feature = "ssse3"))]
+    core::array::from_fn(|i| ((a[i] as i32 * b[i] as i32 + 0x4000) >> 15) as i16)
+}
+
 /// function rotate_left: rotate each element left by b bits
 /// Use negative count to rotate right
 #[inline]
@@ -679,40 +1365,219 @@ pub fn rotate_left(a: Vec16c, b: i32) -> Vec16c {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rand::Rng;
-    #[test]
-    fn test_vec128b() {
+/// vector operator << : shift left each element by the count in the corresponding element of rhs.
+/// Counts outside 0..=7 (negative counts included) give 0
+impl ops::Shl<Vec16c> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shl(self, rhs: Vec16c) -> Self::Output {
+        #[cfg(target_feature = "ssse3")]
         unsafe {
-            let mut arr: [u8; 16] = [0; 16];
-            let mut a128 = Vec128b::new();
-            a128.store(&mut arr as *mut u8);
-            assert_eq!(false, horizontal_or(a128));
+            // multiply by 2^n. pshufb looks up the power of two, counts above 7 hit a zero entry
+            let pow2 = _mm_setr_epi8(1, 2, 4, 8, 16, 32, 64, -128, 0, 0, 0, 0, 0, 0, 0, 0);
+            let n = _mm_min_epu8(rhs.xmm, _mm_set1_epi8(8));
+            self * Vec16c {
+                xmm: _mm_shuffle_epi8(pow2, n),
+            }
+        }
+        #[cfg(not(target_feature = "ssse3"))]
+        unsafe {
+            // shift by 1, 2, 4 and 8 where the corresponding bit of the count is set
+            let n = _mm_min_epu8(rhs.xmm, _mm_set1_epi8(8));
+            let mut x = self;
+            for k in [1, 2, 4, 8] {
+                let bit = _mm_set1_epi8(k as i8);
+                let s = _mm_cmpeq_epi8(_mm_and_si128(n, bit), bit);
+                x = select(Vec16bc { xmm: s }, x << k, x);
+            }
+            x
+        }
+    }
+}
 
-            arr[0] = 1;
-            a128.load(&arr as *const u8);
-            assert_eq!(true, horizontal_or(a128));
-            assert_eq!(false, horizontal_and(a128));
+/// vector operator <<= : shift left by variable counts
+impl ops::ShlAssign<Vec16c> for Vec16c {
+    #[inline]
+    fn shl_assign(&mut self, rhs: Vec16c) {
+        *self = *self << rhs
+    }
+}
 
-            a128 ^= a128;
-            assert_eq!(false, horizontal_or(a128));
+/// vector operator >> : shift right arithmetic each element by the count in the corresponding
+/// element of rhs. Counts outside 0..=7 (negative counts included) fill the element with its sign bit
+impl ops::Shr<Vec16c> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shr(self, rhs: Vec16c) -> Self::Output {
+        unsafe {
+            // shift by 1, 2 and 4 where the corresponding bit of the count is set
+            let n = _mm_min_epu8(rhs.xmm, _mm_set1_epi8(7));
+            let mut x = self;
+            for k in [1, 2, 4] {
+                let bit = _mm_set1_epi8(k as i8);
+                let s = _mm_cmpeq_epi8(_mm_and_si128(n, bit), bit);
+                x = select(Vec16bc { xmm: s }, x >> k, x);
+            }
+            x
         }
     }
+}
 
-    #[test]
-    fn test_vec16c() {
-        let mut rng = rand::thread_rng();
+/// vector operator >>= : shift right arithmetic by variable counts
+impl ops::ShrAssign<Vec16c> for Vec16c {
+    #[inline]
+    fn shr_assign(&mut self, rhs: Vec16c) {
+        *self = *self >> rhs
+    }
+}
 
+impl Vec16c {
+    /// Rotate each element left by the count in the corresponding element of b.
+    /// Counts are taken modulo 8, so negative counts rotate right
+    #[inline]
+    pub fn rotate_left(self, b: Vec16c) -> Vec16c {
         unsafe {
-            let mut a16 = Vec16c::new();
-            let arr1: [i8; 16] = core::array::from_fn(|_| rng.gen_range(0..50));
-            let b16 = Vec16c::set_values(arr1);
-            a16.load(&arr1 as *const [i8] as *const i8);
-            println!("{:?}, {:?}", a16.xmm, b16.xmm);
-            assert_eq!(a16, b16);
-            assert_eq!(a16 & b16, a16 & b16);
+            let mut x = self;
+            for k in [1, 2, 4] {
+                let bit = _mm_set1_epi8(k as i8);
+                let s = _mm_cmpeq_epi8(_mm_and_si128(b.xmm, bit), bit);
+                x = select(Vec16bc { xmm: s }, rotate_left(x, k), x);
+            }
+            x
+        }
+    }
+
+    /// Rotate each element right by the count in the corresponding element of b.
+    /// Counts are taken modulo 8, so negative counts rotate left
+    #[inline]
+    pub fn rotate_right(self, b: Vec16c) -> Vec16c {
+        self.rotate_left(-b)
+    }
+}
+
+/// function popcount: count the number of 1-bits in each element
+#[inline]
+pub fn popcount(a: Vec16c) -> Vec16c {
+    #[cfg(all(target_feature = "avx512bitalg", target_feature = "avx512vl"))]
+    unsafe {
+        Vec16c {
+            xmm: _mm_popcnt_epi8(a.xmm),
+        }
+    }
+    #[cfg(all(
+        target_feature = "ssse3",
+        not(all(target_feature = "avx512bitalg", target_feature = "avx512vl"))
+    ))]
+    unsafe {
+        // look up the bit count of each nibble
+        let table = _mm_setr_epi8(0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4);
+        let nibble = _mm_set1_epi8(0x0F);
+        let lo = _mm_and_si128(a.xmm, nibble);
+        let hi = _mm_and_si128(_mm_srli_epi16(a.xmm, 4), nibble);
+        Vec16c {
+            xmm: _mm_add_epi8(_mm_shuffle_epi8(table, lo), _mm_shuffle_epi8(table, hi)),
+        }
+    }
+    #[cfg(not(target_feature = "ssse3"))]
+    unsafe {
+        // add bits pairwise, then pairs into nibbles, then nibbles into bytes
+        let x = a.xmm;
+        let t = _mm_and_si128(_mm_srli_epi16(x, 1), _mm_set1_epi8(0x55));
+        let x = _mm_sub_epi8(x, t);
+        let m2 = _mm_set1_epi8(0x33);
+        let x = _mm_add_epi8(
+            _mm_and_si128(x, m2),
+            _mm_and_si128(_mm_srli_epi16(x, 2), m2),
+        );
+        let x = _mm_add_epi8(x, _mm_srli_epi16(x, 4));
+        Vec16c {
+            xmm: _mm_and_si128(x, _mm_set1_epi8(0x0F)),
+        }
+    }
+}
+
+/// function leading_zeros: number of leading zero bits in each element. Gives 8 for a zero element
+#[inline]
+pub fn leading_zeros(a: Vec16c) -> Vec16c {
+    unsafe {
+        // smear the highest 1-bit down to bit 0, then count the zeros above it
+        let mut x = a.xmm;
+        x = _mm_or_si128(x, _mm_and_si128(_mm_srli_epi16(x, 1), _mm_set1_epi8(0x7F)));
+        x = _mm_or_si128(x, _mm_and_si128(_mm_srli_epi16(x, 2), _mm_set1_epi8(0x3F)));
+        x = _mm_or_si128(x, _mm_and_si128(_mm_srli_epi16(x, 4), _mm_set1_epi8(0x0F)));
+        popcount(Vec16c {
+            xmm: _mm_xor_si128(x, _mm_set1_epi32(-1)),
+        })
+    }
+}
+
+/// function trailing_zeros: number of trailing zero bits in each element. Gives 8 for a zero element
+#[inline]
+pub fn trailing_zeros(a: Vec16c) -> Vec16c {
+    unsafe {
+        // ~a & (a - 1) has a 1-bit for each trailing zero of a
+        let am1 = _mm_add_epi8(a.xmm, _mm_set1_epi32(-1));
+        popcount(Vec16c {
+            xmm: _mm_andnot_si128(a.xmm, am1),
+        })
+    }
+}
+
+/// function bit_reverse: reverse the order of the bits in each element
+#[inline]
+pub fn bit_reverse(a: Vec16c) -> Vec16c {
+    #[cfg(target_feature = "ssse3")]
+    unsafe {
+        // reverse each nibble by table lookup and swap the two nibbles
+        let table = _mm_setr_epi8(
+            0x0, 0x8, 0x4, 0xC, 0x2, 0xA, 0x6, 0xE, 0x1, 0x9, 0x5, 0xD, 0x3, 0xB, 0x7, 0xF,
+        );
+        let nibble = _mm_set1_epi8(0x0F);
+        let lo = _mm_shuffle_epi8(table, _mm_and_si128(a.xmm, nibble));
+        let hi = _mm_shuffle_epi8(table, _mm_and_si128(_mm_srli_epi16(a.xmm, 4), nibble));
+        Vec16c {
+            xmm: _mm_or_si128(_mm_slli_epi16(_mm_and_si128(lo, nibble), 4), hi),
         }
     }
+    #[cfg(not(target_feature = "ssse3"))]
+    unsafe {
+        // swap nibbles, then bit pairs, then adjacent bits
+        let swap = |m: i8, hi: __m128i, lo: __m128i| {
+            let m = _mm_set1_epi8(m);
+            _mm_or_si128(_mm_and_si128(hi, m), _mm_andnot_si128(m, lo))
+        };
+        let x = a.xmm;
+        let x = swap(0x0F, _mm_srli_epi16(x, 4), _mm_slli_epi16(x, 4));
+        let x = swap(0x33, _mm_srli_epi16(x, 2), _mm_slli_epi16(x, 2));
+        let x = swap(0x55, _mm_srli_epi16(x, 1), _mm_slli_epi16(x, 1));
+        Vec16c { xmm: x }
+    }
 }
+
+/// function lookup16: table lookup with 16 entries.
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 16; i++) result[i] = table[index[i] & 15];
+#[inline]
+pub fn lookup16(index: Vec16c, table: Vec16c) -> Vec16c {
+    #[cfg(target_feature = "ssse3")]
+    unsafe {
+        let index = _mm_and_si128(index.xmm, _mm_set1_epi8(0x0F));
+        Vec16c {
+            xmm: _mm_shuffle_epi8(table.xmm, index),
+        }
+    }
+    #[cfg(not(target_feature = "ssse3"))]
+    unsafe {
+        let mut xi: [i8; 16] = [0; 16];
+        let mut xt: [i8; 16] = [0; 16];
+        index.store(&mut xi as *mut i8);
+        table.store(&mut xt as *mut i8);
+        Vec16c::set_values(core::array::from_fn(|i| xt[(xi[i] & 0x0F) as usize]))
+    }
+}
+
+#[cfg(test)]
+#[path = "vectori128_tests.rs"]
+mod tests;
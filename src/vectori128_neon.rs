@@ -0,0 +1,1298 @@
+//! NEON implementation of the 128-bit vector classes for AArch64, with the same interface and
+//! results as the SSE version
+
+use core::arch::aarch64::*;
+use core::hash::{Hash, Hasher};
+use core::ops;
+
+#[derive(Copy, Clone)]
+pub struct Vec128b {
+    reg: uint8x16_t,
+}
+
+impl Vec128b {
+    pub const LEN: usize = 128;
+
+    /// All bits zero
+    pub const ZERO: Self = Self::from_array_const([0; 16]);
+
+    /// All bits one
+    pub const ONES: Self = Self::from_array_const([0xFF; 16]);
+
+    /// Constructor from an array of bytes that can be used in constant expressions
+    pub const fn from_array_const(x: [u8; 16]) -> Self {
+        Vec128b {
+            reg: unsafe { core::mem::transmute::<[u8; 16], uint8x16_t>(x) },
+        }
+    }
+
+    pub fn new() -> Self {
+        Vec128b {
+            reg: unsafe { vdupq_n_u8(0) },
+        }
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Loads from unaligned array
+    #[inline]
+    pub unsafe fn load(&mut self, mem_addr: *const u8) {
+        self.reg = vld1q_u8(mem_addr);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// mem_addr must be aligned by 16
+    #[inline]
+    pub unsafe fn load_aligned(&mut self, mem_addr: *const u8) {
+        self.reg = vld1q_u8(mem_addr);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Stores into unaligned array
+    #[inline]
+    pub unsafe fn store(&self, mem_addr: *mut u8) {
+        vst1q_u8(mem_addr, self.reg);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// mem_addr must be aligned by 16
+    #[inline]
+    pub unsafe fn store_aligned(&self, mem_addr: *mut u8) {
+        vst1q_u8(mem_addr, self.reg);
+    }
+
+    /// Shift all bytes up by N positions, zero-filling the lowest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_up<const N: i32>(self) -> Self {
+        Vec128b {
+            reg: shift_bytes_up_u8::<N>(self.reg),
+        }
+    }
+
+    /// Shift all bytes down by N positions, zero-filling the highest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_down<const N: i32>(self) -> Self {
+        Vec128b {
+            reg: shift_bytes_down_u8::<N>(self.reg),
+        }
+    }
+
+    /// Extract 16 consecutive bytes starting at byte N from the 32-byte concatenation hi:lo,
+    /// lo being the lower half. N must be in 0..=16
+    #[inline]
+    pub fn align_right<const N: i32>(hi: Self, lo: Self) -> Self {
+        Vec128b {
+            reg: align_right_u8::<N>(hi.reg, lo.reg),
+        }
+    }
+}
+
+impl Default for Vec128b {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for Vec128b {
+    fn eq(&self, other: &Self) -> bool {
+        Vec16c::from(*self) == Vec16c::from(*other)
+    }
+}
+
+impl Eq for Vec128b {}
+
+impl Hash for Vec128b {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Vec16c::from(*self).hash(state)
+    }
+}
+
+impl ops::BitAnd for Vec128b {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, other: Vec128b) -> Vec128b {
+        Vec128b {
+            reg: unsafe { vandq_u8(self.reg, other.reg) },
+        }
+    }
+}
+
+impl ops::BitOr for Vec128b {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, other: Vec128b) -> Vec128b {
+        Vec128b {
+            reg: unsafe { vorrq_u8(self.reg, other.reg) },
+        }
+    }
+}
+
+impl ops::BitXor for Vec128b {
+    type Output = Self;
+
+    #[inline]
+    fn bitxor(self, other: Vec128b) -> Vec128b {
+        Vec128b {
+            reg: unsafe { veorq_u8(self.reg, other.reg) },
+        }
+    }
+}
+
+impl ops::Not for Vec128b {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Vec128b {
+        Vec128b {
+            reg: unsafe { vmvnq_u8(self.reg) },
+        }
+    }
+}
+
+impl ops::BitAndAssign for Vec128b {
+    #[inline]
+    fn bitand_assign(&mut self, other: Vec128b) {
+        *self = *self & other
+    }
+}
+
+impl ops::BitOrAssign for Vec128b {
+    #[inline]
+    fn bitor_assign(&mut self, other: Vec128b) {
+        *self = *self | other
+    }
+}
+
+impl ops::BitXorAssign for Vec128b {
+    #[inline]
+    fn bitxor_assign(&mut self, other: Vec128b) {
+        *self = *self ^ other
+    }
+}
+
+/// function andnot: a & ~ b
+#[inline]
+pub fn andnot(a: Vec128b, b: Vec128b) -> Vec128b {
+    Vec128b {
+        reg: unsafe { vbicq_u8(a.reg, b.reg) },
+    }
+}
+
+/// The byte indexes 0, 1, ..., 15
+#[inline]
+fn iota() -> uint8x16_t {
+    let x: [u8; 16] = core::array::from_fn(|i| i as u8);
+    unsafe { vld1q_u8(x.as_ptr()) }
+}
+
+// The byte shifts look up each byte in a table, as the indexes outside the table give 0.
+// This also handles N = 16, which vextq does not accept
+
+/// Shift the whole register up by N bytes (towards higher element index), zero-filling the low bytes
+#[inline]
+fn shift_bytes_up_u8<const N: i32>(a: uint8x16_t) -> uint8x16_t {
+    const { assert!(N >= 0 && N <= 16, "byte shift count must be in 0..=16") };
+    // the indexes below N wrap around to 240 and up
+    unsafe { vqtbl1q_u8(a, vsubq_u8(iota(), vdupq_n_u8(N as u8))) }
+}
+
+/// Shift the whole register down by N bytes (towards lower element index), zero-filling the high bytes
+#[inline]
+fn shift_bytes_down_u8<const N: i32>(a: uint8x16_t) -> uint8x16_t {
+    const { assert!(N >= 0 && N <= 16, "byte shift count must be in 0..=16") };
+    unsafe { vqtbl1q_u8(a, vaddq_u8(iota(), vdupq_n_u8(N as u8))) }
+}
+
+/// Concatenate hi:lo into 32 bytes and extract the 16 bytes starting at byte N.
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 16; i++) result[i] = i + N < 16 ? lo[i + N] : hi[i + N - 16];
+#[inline]
+fn align_right_u8<const N: i32>(hi: uint8x16_t, lo: uint8x16_t) -> uint8x16_t {
+    const { assert!(N >= 0 && N <= 16, "alignment must be in 0..=16") };
+    unsafe { vqtbl2q_u8(uint8x16x2_t(lo, hi), vaddq_u8(iota(), vdupq_n_u8(N as u8))) }
+}
+
+/// Returns false if at least one bit is 0
+#[inline]
+pub fn horizontal_and(a: Vec128b) -> bool {
+    unsafe { vminvq_u8(a.reg) == 0xFF }
+}
+
+/// Returns true if at least one bit is 1
+#[inline]
+pub fn horizontal_or(a: Vec128b) -> bool {
+    unsafe { vmaxvq_u8(a.reg) != 0 }
+}
+
+#[derive(Copy, Clone)]
+pub struct Vec16c {
+    pub(crate) reg: int8x16_t,
+}
+
+impl Vec16c {
+    pub const LEN: usize = 16;
+
+    /// All elements zero
+    pub const ZERO: Self = Self::from_array_const([0; 16]);
+
+    /// All bits one, i.e. all elements -1
+    pub const ONES: Self = Self::from_array_const([-1; 16]);
+
+    /// Constructor from an array that can be used in constant expressions
+    pub const fn from_array_const(x: [i8; 16]) -> Self {
+        Vec16c {
+            reg: unsafe { core::mem::transmute::<[i8; 16], int8x16_t>(x) },
+        }
+    }
+
+    pub fn new() -> Self {
+        Vec16c {
+            reg: unsafe { vdupq_n_s8(0) },
+        }
+    }
+
+    /// Constructor to broadcast the same value into all elements:
+    pub fn set_value(a: i8) -> Self {
+        Vec16c {
+            reg: unsafe { vdupq_n_s8(a) },
+        }
+    }
+
+    /// Constructor to build from all elements:
+    pub fn set_values(a: [i8; 16]) -> Self {
+        Vec16c {
+            reg: unsafe { vld1q_s8(a.as_ptr()) },
+        }
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Member function to load from array (unaligned)
+    #[inline]
+    pub unsafe fn load(&mut self, mem_addr: *const i8) {
+        self.reg = vld1q_s8(mem_addr);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// mem_addr must be aligned by 16
+    #[inline]
+    pub unsafe fn load_aligned(&mut self, mem_addr: *const i8) {
+        self.reg = vld1q_s8(mem_addr);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Stores into unaligned array
+    #[inline]
+    pub unsafe fn store(&self, mem_addr: *mut i8) {
+        vst1q_s8(mem_addr, self.reg);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// mem_addr must be aligned by 16
+    #[inline]
+    pub unsafe fn store_aligned(&self, mem_addr: *mut i8) {
+        vst1q_s8(mem_addr, self.reg);
+    }
+
+    ///# Safety: n bytes in the mem_addr must be valid
+    ///
+    /// Partial load. Load n elements and set the rest to 0
+    #[inline]
+    pub unsafe fn load_partial(&mut self, n: usize, arr: &[i8]) {
+        let n = n.min(16);
+        let mut x: [i8; 16] = [0; 16];
+        x[..n].copy_from_slice(&arr[..n]);
+        self.load(x.as_ptr());
+    }
+
+    ///# Safety: at least n bytes must be allocated to the mem_addr
+    ///
+    /// Partial store. Store n elements
+    #[inline]
+    pub unsafe fn store_partial(&mut self, n: usize, mem_addr: *mut i8) {
+        let mut s: [i8; 16] = [0; 16];
+        self.store(s.as_mut_ptr());
+        core::ptr::copy_nonoverlapping(s.as_ptr(), mem_addr, n.min(16));
+    }
+
+    ///# Safety: obvious
+    ///
+    /// cut off vector to n elements. The last 16-n elements are set to zero
+    #[inline]
+    pub unsafe fn cutoff(&mut self, n: usize) {
+        let keep = vcltq_u8(iota(), vdupq_n_u8(n.min(16) as u8));
+        self.reg = vandq_s8(self.reg, vreinterpretq_s8_u8(keep));
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Member function to change a single element in vector
+    #[inline]
+    pub unsafe fn insert(&mut self, index: isize, value: i8) {
+        let mut x: [i8; 16] = [0; 16];
+        self.store(x.as_mut_ptr());
+        x[(index & 0x0F) as usize] = value;
+        self.load(x.as_ptr());
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Member function extract a single element from vector
+    #[inline]
+    pub unsafe fn extract(&mut self, index: usize) -> i8 {
+        let mut x: [i8; 16] = [0; 16];
+        self.store(x.as_mut_ptr());
+        x[index & 0x0F]
+    }
+
+    /// Shift all bytes up by N positions, zero-filling the lowest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_up<const N: i32>(self) -> Self {
+        unsafe {
+            Vec16c {
+                reg: vreinterpretq_s8_u8(shift_bytes_up_u8::<N>(vreinterpretq_u8_s8(self.reg))),
+            }
+        }
+    }
+
+    /// Shift all bytes down by N positions, zero-filling the highest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_down<const N: i32>(self) -> Self {
+        unsafe {
+            Vec16c {
+                reg: vreinterpretq_s8_u8(shift_bytes_down_u8::<N>(vreinterpretq_u8_s8(self.reg))),
+            }
+        }
+    }
+
+    /// Extract 16 consecutive bytes starting at byte N from the 32-byte concatenation hi:lo,
+    /// lo being the lower half. N must be in 0..=16
+    #[inline]
+    pub fn align_right<const N: i32>(hi: Self, lo: Self) -> Self {
+        unsafe {
+            Vec16c {
+                reg: vreinterpretq_s8_u8(align_right_u8::<N>(
+                    vreinterpretq_u8_s8(hi.reg),
+                    vreinterpretq_u8_s8(lo.reg),
+                )),
+            }
+        }
+    }
+}
+
+impl Default for Vec16c {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Vec16bc {
+    reg: uint8x16_t,
+}
+
+impl Vec16bc {
+    /// All elements false
+    pub const ZERO: Self = Self::from_array_const([false; 16]);
+
+    /// All elements true
+    pub const ONES: Self = Self::from_array_const([true; 16]);
+
+    /// Constructor from an array that can be used in constant expressions
+    pub const fn from_array_const(x: [bool; 16]) -> Self {
+        let mut y = [0i8; 16];
+        let mut i = 0;
+        while i < 16 {
+            y[i] = -(x[i] as i8);
+            i += 1;
+        }
+        Vec16bc {
+            reg: unsafe { core::mem::transmute::<[i8; 16], uint8x16_t>(y) },
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_values(
+        x0: bool,
+        x1: bool,
+        x2: bool,
+        x3: bool,
+        x4: bool,
+        x5: bool,
+        x6: bool,
+        x7: bool,
+        x8: bool,
+        x9: bool,
+        x10: bool,
+        x11: bool,
+        x12: bool,
+        x13: bool,
+        x14: bool,
+        x15: bool,
+    ) -> Self {
+        let x = [
+            x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, x12, x13, x14, x15,
+        ];
+        Vec16bc {
+            reg: unsafe { vreinterpretq_u8_s8(Vec16c::set_values(x.map(|b| -(b as i8))).reg) },
+        }
+    }
+
+    /// Each element must be either 0 (false) or 0xFF (true)
+    pub fn set_value(x: uint8x16_t) -> Self {
+        Vec16bc { reg: x }
+    }
+}
+
+impl Default for Vec16bc {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl PartialEq for Vec16bc {
+    fn eq(&self, other: &Self) -> bool {
+        Vec16c::from(*self) == Vec16c::from(*other)
+    }
+}
+
+impl Eq for Vec16bc {}
+
+impl Hash for Vec16bc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Vec16c::from(*self).hash(state)
+    }
+}
+
+/// Get the bits of a boolean vector as an integer, bit i corresponding to element i
+#[inline]
+pub fn to_bits(x: Vec16bc) -> u16 {
+    unsafe {
+        // keep bit i % 8 of each element that has the sign bit set, then add up each half
+        let weights: [u8; 16] = core::array::from_fn(|i| 1 << (i % 8));
+        let sign = vreinterpretq_u8_s8(vshrq_n_s8::<7>(vreinterpretq_s8_u8(x.reg)));
+        let bits = vandq_u8(sign, vld1q_u8(weights.as_ptr()));
+        vaddv_u8(vget_low_u8(bits)) as u16 | (vaddv_u8(vget_high_u8(bits)) as u16) << 8
+    }
+}
+
+/// Horizontal find first: index of the first true element, or None if all are false
+#[inline]
+pub fn horizontal_find_first(x: Vec16bc) -> Option<usize> {
+    let bits = to_bits(x);
+    if bits == 0 {
+        None
+    } else {
+        Some(bits.trailing_zeros() as usize)
+    }
+}
+
+/// Horizontal count: number of true elements
+#[inline]
+pub fn horizontal_count(x: Vec16bc) -> u32 {
+    to_bits(x).count_ones()
+}
+
+/// vector operator & : and of boolean vectors
+impl ops::BitAnd for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, other: Self) -> Self {
+        Vec16bc {
+            reg: unsafe { vandq_u8(self.reg, other.reg) },
+        }
+    }
+}
+
+/// vector operator | : or of boolean vectors
+impl ops::BitOr for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, other: Self) -> Self {
+        Vec16bc {
+            reg: unsafe { vorrq_u8(self.reg, other.reg) },
+        }
+    }
+}
+
+/// vector operator ^ : xor of boolean vectors
+impl ops::BitXor for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitxor(self, other: Self) -> Self {
+        Vec16bc {
+            reg: unsafe { veorq_u8(self.reg, other.reg) },
+        }
+    }
+}
+
+/// vector operator ! : not of a boolean vector
+impl ops::Not for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Self {
+        Vec16bc {
+            reg: unsafe { vmvnq_u8(self.reg) },
+        }
+    }
+}
+
+/*****************************************************************************
+*
+*          Define operators for Vec16c
+*
+*****************************************************************************/
+
+/// Convert Vec16bc to Vec16c
+impl From<Vec16bc> for Vec16c {
+    fn from(a: Vec16bc) -> Self {
+        Vec16c {
+            reg: unsafe { vreinterpretq_s8_u8(a.reg) },
+        }
+    }
+}
+
+/*****************************************************************************
+*
+*          Conversions to and from the raw register types
+*
+*****************************************************************************/
+
+// Element i of Vec16c and Vec16bc is lane i of the register, as with vld1q from an array.
+// Conversions between vectors of the same size keep all the bits and compile to nothing.
+// There are no Vec4i, Vec4f or 256 and 512-bit vector classes yet. Vec128b converts to and from
+// float32x4_t and float64x2_t, so that the same bits can be used as floats with the intrinsics
+
+/// Wrap a raw register
+impl From<uint8x16_t> for Vec128b {
+    #[inline]
+    fn from(x: uint8x16_t) -> Self {
+        Vec128b { reg: x }
+    }
+}
+
+/// Get the raw register
+impl From<Vec128b> for uint8x16_t {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        a.reg
+    }
+}
+
+/// Wrap a raw register, element i being lane i
+impl From<int8x16_t> for Vec16c {
+    #[inline]
+    fn from(x: int8x16_t) -> Self {
+        Vec16c { reg: x }
+    }
+}
+
+/// Get the raw register, element i being lane i
+impl From<Vec16c> for int8x16_t {
+    #[inline]
+    fn from(a: Vec16c) -> Self {
+        a.reg
+    }
+}
+
+/// Wrap a raw register. Each lane must be either 0 (false) or 0xFF (true), as for set_value
+impl From<uint8x16_t> for Vec16bc {
+    #[inline]
+    fn from(x: uint8x16_t) -> Self {
+        Vec16bc { reg: x }
+    }
+}
+
+/// Get the raw register, with 0 (false) or 0xFF (true) in lane i for element i
+impl From<Vec16bc> for uint8x16_t {
+    #[inline]
+    fn from(a: Vec16bc) -> Self {
+        a.reg
+    }
+}
+
+/// Reinterpret the 128 bits as 16 elements of 8 bits
+impl From<Vec128b> for Vec16c {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        Vec16c {
+            reg: unsafe { vreinterpretq_s8_u8(a.reg) },
+        }
+    }
+}
+
+/// Reinterpret the 16 elements as 128 bits
+impl From<Vec16c> for Vec128b {
+    #[inline]
+    fn from(a: Vec16c) -> Self {
+        Vec128b {
+            reg: unsafe { vreinterpretq_u8_s8(a.reg) },
+        }
+    }
+}
+
+/// Reinterpret the 16 boolean elements as 128 bits
+impl From<Vec16bc> for Vec128b {
+    #[inline]
+    fn from(a: Vec16bc) -> Self {
+        Vec128b { reg: a.reg }
+    }
+}
+
+/// Reinterpret 4 floats as 128 bits, bytes 4i to 4i+3 being float i
+impl From<float32x4_t> for Vec128b {
+    #[inline]
+    fn from(x: float32x4_t) -> Self {
+        Vec128b {
+            reg: unsafe { vreinterpretq_u8_f32(x) },
+        }
+    }
+}
+
+/// Reinterpret the 128 bits as 4 floats, bytes 4i to 4i+3 being float i
+impl From<Vec128b> for float32x4_t {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        unsafe { vreinterpretq_f32_u8(a.reg) }
+    }
+}
+
+/// Reinterpret 2 doubles as 128 bits, bytes 8i to 8i+7 being double i
+impl From<float64x2_t> for Vec128b {
+    #[inline]
+    fn from(x: float64x2_t) -> Self {
+        Vec128b {
+            reg: unsafe { vreinterpretq_u8_f64(x) },
+        }
+    }
+}
+
+/// Reinterpret the 128 bits as 2 doubles, bytes 8i to 8i+7 being double i
+impl From<Vec128b> for float64x2_t {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        unsafe { vreinterpretq_f64_u8(a.reg) }
+    }
+}
+
+/// vector operator + : add element by element
+impl ops::Add for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        Self {
+            reg: unsafe { vaddq_s8(self.reg, other.reg) },
+        }
+    }
+}
+
+/// vector operator += : add
+impl ops::AddAssign for Vec16c {
+    #[inline]
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other
+    }
+}
+
+/// vector operator - : subtract element by element
+impl ops::Sub for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, other: Self) -> Self {
+        Self {
+            reg: unsafe { vsubq_s8(self.reg, other.reg) },
+        }
+    }
+}
+
+/// vector operator - : unary minus
+impl ops::Neg for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        Self {
+            reg: unsafe { vnegq_s8(self.reg) },
+        }
+    }
+}
+
+///vector operator -= : add
+impl ops::SubAssign for Vec16c {
+    #[inline]
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other
+    }
+}
+
+/// vector operator * : multiply element by element
+impl ops::Mul for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, other: Self) -> Self {
+        Self {
+            reg: unsafe { vmulq_s8(self.reg, other.reg) },
+        }
+    }
+}
+
+/// vector operator *= : multiply
+impl ops::MulAssign for Vec16c {
+    #[inline]
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other
+    }
+}
+
+/// vector operator << : shift left all elements
+impl ops::Shl<i32> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shl(self, rhs: i32) -> Self::Output {
+        if (rhs as u32) < 8 {
+            Self {
+                reg: unsafe { vshlq_s8(self.reg, vdupq_n_s8(rhs as i8)) },
+            }
+        } else {
+            Self::new()
+        }
+    }
+}
+
+/// vector operator <<= : shift left
+impl ops::ShlAssign<i32> for Vec16c {
+    #[inline]
+    fn shl_assign(&mut self, rhs: i32) {
+        *self = *self << rhs
+    }
+}
+
+/// vector operator >> : shift right arithmetic all elements
+impl ops::Shr<i32> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shr(self, rhs: i32) -> Self::Output {
+        // vshlq shifts right by negative counts
+        let n = (rhs as u32).min(7) as i8;
+        Self {
+            reg: unsafe { vshlq_s8(self.reg, vdupq_n_s8(-n)) },
+        }
+    }
+}
+
+/// vector operator >>= : shift right arithmetic
+impl ops::ShrAssign<i32> for Vec16c {
+    #[inline]
+    fn shr_assign(&mut self, rhs: i32) {
+        *self = *self >> rhs
+    }
+}
+
+/// vector operator & : bitwise and
+impl ops::BitAnd for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, other: Self) -> Self {
+        Self {
+            reg: unsafe { vandq_s8(self.reg, other.reg) },
+        }
+    }
+}
+
+/// vector operator &= : bitwise and
+impl ops::BitAndAssign for Vec16c {
+    #[inline]
+    fn bitand_assign(&mut self, other: Self) {
+        *self = *self & other
+    }
+}
+
+/// vector operator == : equality
+impl PartialEq for Vec16c {
+    fn eq(&self, other: &Self) -> bool {
+        unsafe { vminvq_u8(vceqq_s8(self.reg, other.reg)) == 0xFF }
+    }
+}
+
+impl Eq for Vec16c {}
+
+/// Hash of the elements, in agreement with ==
+impl Hash for Vec16c {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_array().hash(state)
+    }
+}
+
+/// function compare_eq: a[i] == b[i] for each element
+#[inline]
+pub fn compare_eq(a: Vec16c, b: Vec16c) -> Vec16bc {
+    Vec16bc {
+        reg: unsafe { vceqq_s8(a.reg, b.reg) },
+    }
+}
+
+/// function compare_gt: a[i] > b[i] for each element, signed
+#[inline]
+pub fn compare_gt(a: Vec16c, b: Vec16c) -> Vec16bc {
+    Vec16bc {
+        reg: unsafe { vcgtq_s8(a.reg, b.reg) },
+    }
+}
+
+/// function compare_lt: a[i] < b[i] for each element, signed
+#[inline]
+pub fn compare_lt(a: Vec16c, b: Vec16c) -> Vec16bc {
+    Vec16bc {
+        reg: unsafe { vcltq_s8(a.reg, b.reg) },
+    }
+}
+
+/// Select between two operands. Each element of s must be either 0 (false) or -1 (true).
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 16; i++) result[i] = s[i] ? a[i] : b[i];
+#[inline]
+pub fn select(s: Vec16bc, a: Vec16c, b: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vbslq_s8(s.reg, a.reg, b.reg) },
+    }
+}
+
+/// Conditional add: For all vector elements i: result[i] = f[i] ? (a[i] + b[i]) : a[i]
+#[inline]
+pub fn if_add(f: Vec16bc, a: Vec16c, b: Vec16c) -> Vec16c {
+    a + ((Vec16c::from(f)) & b)
+}
+
+/// Conditional sub: For all vector elements i: result[i] = f[i] ? (a[i] - b[i]) : a[i]
+#[inline]
+pub fn if_sub(f: Vec16bc, a: Vec16c, b: Vec16c) -> Vec16c {
+    a - ((Vec16c::from(f)) & b)
+}
+
+/// Conditional mul: For all vector elements i: result[i] = f[i] ? (a[i] * b[i]) : a[i]
+#[inline]
+pub fn if_mul(f: Vec16bc, a: Vec16c, b: Vec16c) -> Vec16c {
+    select(f, a * b, a)
+}
+
+/// Horizontal add: Calculates the sum of all vector elements. Overflow will wrap around
+#[inline]
+pub fn horizontal_add(a: Vec16c) -> i32 {
+    unsafe { vaddvq_s8(a.reg) as i32 }
+}
+
+/// Horizontal add extended: Calculates the sum of all vector elements.
+/// Each element is sign-extended before addition to avoid overflow
+#[inline]
+pub fn horizontal_add_x(a: Vec16c) -> i32 {
+    unsafe { vaddlvq_s8(a.reg) as i32 }
+}
+
+/// Horizontal min: Smallest element of the vector
+#[inline]
+pub fn horizontal_min(a: Vec16c) -> i8 {
+    unsafe { vminvq_s8(a.reg) }
+}
+
+/// Horizontal max: Largest element of the vector
+#[inline]
+pub fn horizontal_max(a: Vec16c) -> i8 {
+    unsafe { vmaxvq_s8(a.reg) }
+}
+
+/// function add_saturated: add element by element, signed with saturatio
+#[inline]
+pub fn add_saturated(a: Vec16c, b: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vqaddq_s8(a.reg, b.reg) },
+    }
+}
+
+/// function sub_saturated: subtract element by element, signed with saturation
+#[inline]
+pub fn sub_saturated(a: Vec16c, b: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vqsubq_s8(a.reg, b.reg) },
+    }
+}
+
+/// function max: a > b ? a : b
+#[inline]
+pub fn max(a: Vec16c, b: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vmaxq_s8(a.reg, b.reg) },
+    }
+}
+
+/// function min: a < b ? a : b
+#[inline]
+pub fn min(a: Vec16c, b: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vminq_s8(a.reg, b.reg) },
+    }
+}
+
+/// function abs: a >= 0 ? a : -a
+#[inline]
+pub fn abs(a: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vabsq_s8(a.reg) },
+    }
+}
+
+/// function abs_saturated: same as abs, saturate if overflow
+#[inline]
+pub fn abs_saturated(a: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vqabsq_s8(a.reg) },
+    }
+}
+
+/// function avg: (a + b + 1) >> 1, computed without overflow
+#[inline]
+pub fn avg(a: Vec16c, b: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vrhaddq_s8(a.reg, b.reg) },
+    }
+}
+
+/// function abs_diff: |a - b| computed without overflow.
+/// The result is in 0..=255, so it is returned as unsigned bytes in Vec128b, as from abs_diff_u8
+#[inline]
+pub fn abs_diff(a: Vec16c, b: Vec16c) -> Vec128b {
+    Vec128b {
+        reg: unsafe { vreinterpretq_u8_s8(vabdq_s8(a.reg, b.reg)) },
+    }
+}
+
+/// function sad: sum of absolute differences.
+/// Returns the sum of |a[i] - b[i]| over elements 0..8 and over elements 8..16
+#[inline]
+pub fn sad(a: Vec16c, b: Vec16c) -> [u64; 2] {
+    unsafe {
+        let d = vreinterpretq_u8_s8(vabdq_s8(a.reg, b.reg));
+        [
+            vaddlv_u8(vget_low_u8(d)) as u64,
+            vaddlv_u8(vget_high_u8(d)) as u64,
+        ]
+    }
+}
+
+/// function mpsad: multiple sums of absolute differences (mpsadbw), for block matching.
+/// Compares the 4-element block of b starting at 4 * (IMM8 & 3) with the eight 4-element windows
+/// of a starting at elements 4 * ((IMM8 >> 2) & 1) + i. Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = sum(j = 0..4, |a[aoff + i + j] - b[boff + j]|);
+#[inline]
+pub fn mpsad<const IMM8: i32>(a: Vec16c, b: Vec16c) -> [u16; 8] {
+    const {
+        assert!(
+            IMM8 >= 0 && IMM8 <= 7,
+            "mpsad offset selector must be in 0..=7"
+        )
+    };
+    let mut xa: [i8; 16] = [0; 16];
+    let mut xb: [i8; 16] = [0; 16];
+    unsafe {
+        a.store(xa.as_mut_ptr());
+        b.store(xb.as_mut_ptr());
+    }
+    let aoff = 4 * ((IMM8 as usize >> 2) & 1);
+    let boff = 4 * (IMM8 as usize & 3);
+    core::array::from_fn(|i| {
+        (0..4)
+            .map(|j| xa[aoff + i + j].abs_diff(xb[boff + j]) as u16)
+            .sum()
+    })
+}
+
+// Unsigned versions for u8 data such as pixels. The elements of the Vec128b operands are read
+// as u8, as by Vec128b::to_array
+
+/// function avg_u8: (a + b + 1) >> 1 for unsigned elements, computed without overflow
+#[inline]
+pub fn avg_u8(a: Vec128b, b: Vec128b) -> Vec128b {
+    Vec128b {
+        reg: unsafe { vrhaddq_u8(a.reg, b.reg) },
+    }
+}
+
+/// function abs_diff_u8: |a - b| for unsigned elements
+#[inline]
+pub fn abs_diff_u8(a: Vec128b, b: Vec128b) -> Vec128b {
+    Vec128b {
+        reg: unsafe { vabdq_u8(a.reg, b.reg) },
+    }
+}
+
+/// function sad_u8: sum of absolute differences of unsigned elements.
+/// Returns the sum of |a[i] - b[i]| over elements 0..8 and over elements 8..16
+#[inline]
+pub fn sad_u8(a: Vec128b, b: Vec128b) -> [u64; 2] {
+    unsafe {
+        let d = vabdq_u8(a.reg, b.reg);
+        [
+            vaddlv_u8(vget_low_u8(d)) as u64,
+            vaddlv_u8(vget_high_u8(d)) as u64,
+        ]
+    }
+}
+
+/// function mpsad_u8: multiple sums of absolute differences of unsigned elements (mpsadbw).
+/// The blocks are selected by IMM8 as in mpsad
+#[inline]
+pub fn mpsad_u8<const IMM8: i32>(a: Vec128b, b: Vec128b) -> [u16; 8] {
+    const {
+        assert!(
+            IMM8 >= 0 && IMM8 <= 7,
+            "mpsad offset selector must be in 0..=7"
+        )
+    };
+    let (xa, xb) = (a.to_array(), b.to_array());
+    let aoff = 4 * ((IMM8 as usize >> 2) & 1);
+    let boff = 4 * (IMM8 as usize & 3);
+    core::array::from_fn(|i| {
+        (0..4)
+            .map(|j| xa[aoff + i + j].abs_diff(xb[boff + j]) as u16)
+            .sum()
+    })
+}
+
+/// function mul_hi: high 8 bits of the 16-bit product of each pair of elements, (a * b) >> 8
+#[inline]
+pub fn mul_hi(a: Vec16c, b: Vec16c) -> Vec16c {
+    unsafe {
+        // the high bytes of the 16-bit products are the odd bytes
+        let lo = vmull_s8(vget_low_s8(a.reg), vget_low_s8(b.reg));
+        let hi = vmull_high_s8(a.reg, b.reg);
+        Vec16c {
+            reg: vuzp2q_s8(vreinterpretq_s8_s16(lo), vreinterpretq_s8_s16(hi)),
+        }
+    }
+}
+
+/// function mul_wide: full 16-bit products of all elements.
+/// Returns the products of elements 0..8 and of elements 8..16
+#[inline]
+pub fn mul_wide(a: Vec16c, b: Vec16c) -> ([i16; 8], [i16; 8]) {
+    unsafe {
+        let mut lo: [i16; 8] = [0; 8];
+        let mut hi: [i16; 8] = [0; 8];
+        vst1q_s16(
+            lo.as_mut_ptr(),
+            vmull_s8(vget_low_s8(a.reg), vget_low_s8(b.reg)),
+        );
+        vst1q_s16(hi.as_mut_ptr(), vmull_high_s8(a.reg, b.reg));
+        (lo, hi)
+    }
+}
+
+/// function madd_u8i8: products of the unsigned elements of a and the signed elements of b,
+/// added in pairs with signed saturation (pmaddubsw). Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = saturate_i16(a[2i] * b[2i] + a[2i+1] * b[2i+1]);
+#[inline]
+pub fn madd_u8i8(a: Vec128b, b: Vec16c) -> [i16; 8] {
+    unsafe {
+        // the products fit in i16 and the pairwise sums in i32, which are then saturated
+        let (ua, sb) = (a.reg, b.reg);
+        let alo = vreinterpretq_s16_u16(vmovl_u8(vget_low_u8(ua)));
+        let ahi = vreinterpretq_s16_u16(vmovl_high_u8(ua));
+        let plo = vmulq_s16(alo, vmovl_s8(vget_low_s8(sb)));
+        let phi = vmulq_s16(ahi, vmovl_high_s8(sb));
+        let sum = vqmovn_high_s32(vqmovn_s32(vpaddlq_s16(plo)), vpaddlq_s16(phi));
+        let mut x: [i16; 8] = [0; 8];
+        vst1q_s16(x.as_mut_ptr(), sum);
+        x
+    }
+}
+
+/// function madd_i16: products of signed 16-bit elements added in pairs (pmaddwd).
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 4; i++) result[i] = a[2i] * b[2i] + a[2i+1] * b[2i+1];
+/// Only -32768 * -32768 + -32768 * -32768 overflows, and wraps around to i32::MIN
+#[inline]
+pub fn madd_i16(a: [i16; 8], b: [i16; 8]) -> [i32; 4] {
+    unsafe {
+        let (va, vb) = (vld1q_s16(a.as_ptr()), vld1q_s16(b.as_ptr()));
+        let plo = vmull_s16(vget_low_s16(va), vget_low_s16(vb));
+        let phi = vmull_high_s16(va, vb);
+        let mut x: [i32; 4] = [0; 4];
+        vst1q_s32(x.as_mut_ptr(), vpaddq_s32(plo, phi));
+        x
+    }
+}
+
+/// function mul_hrs: high half of the product of signed 16-bit elements in fixed point, rounded
+/// (pmulhrsw). Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = (a[i] * b[i] + 0x4000) >> 15;
+/// Only -32768 * -32768 overflows, and wraps around to -32768
+#[inline]
+pub fn mul_hrs(a: [i16; 8], b: [i16; 8]) -> [i16; 8] {
+    unsafe {
+        // vrshrn rounds and narrows without saturation, as pmulhrsw
+        let (va, vb) = (vld1q_s16(a.as_ptr()), vld1q_s16(b.as_ptr()));
+        let lo = vrshrn_n_s32::<15>(vmull_s16(vget_low_s16(va), vget_low_s16(vb)));
+        let r = vrshrn_high_n_s32::<15>(lo, vmull_high_s16(va, vb));
+        let mut x: [i16; 8] = [0; 8];
+        vst1q_s16(x.as_mut_ptr(), r);
+        x
+    }
+}
+
+/// Rotate each element of a left by the count in the corresponding element of n, in 0..=7
+#[inline]
+fn rotate_left_u8(a: int8x16_t, n: int8x16_t) -> int8x16_t {
+    unsafe {
+        // vshlq_u8 shifts right by negative counts, and n - 8 = -8 gives 0
+        let x = vreinterpretq_u8_s8(a);
+        let left = vshlq_u8(x, n);
+        let right = vshlq_u8(x, vsubq_s8(n, vdupq_n_s8(8)));
+        vreinterpretq_s8_u8(vorrq_u8(left, right))
+    }
+}
+
+/// function rotate_left: rotate each element left by b bits
+/// Use negative count to rotate right
+#[inline]
+pub fn rotate_left(a: Vec16c, b: i32) -> Vec16c {
+    Vec16c {
+        reg: rotate_left_u8(a.reg, unsafe { vdupq_n_s8((b & 7) as i8) }),
+    }
+}
+
+/// vector operator << : shift left each element by the count in the corresponding element of rhs.
+/// Counts outside 0..=7 (negative counts included) give 0
+impl ops::Shl<Vec16c> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shl(self, rhs: Vec16c) -> Self::Output {
+        unsafe {
+            // a left shift by 8 gives 0. Limit the counts so that none is read as negative
+            let n = vminq_u8(vreinterpretq_u8_s8(rhs.reg), vdupq_n_u8(8));
+            Self {
+                reg: vshlq_s8(self.reg, vreinterpretq_s8_u8(n)),
+            }
+        }
+    }
+}
+
+/// vector operator <<= : shift left by variable counts
+impl ops::ShlAssign<Vec16c> for Vec16c {
+    #[inline]
+    fn shl_assign(&mut self, rhs: Vec16c) {
+        *self = *self << rhs
+    }
+}
+
+/// vector operator >> : shift right arithmetic each element by the count in the corresponding
+/// element of rhs. Counts outside 0..=7 (negative counts included) fill the element with its sign bit
+impl ops::Shr<Vec16c> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shr(self, rhs: Vec16c) -> Self::Output {
+        unsafe {
+            let n = vminq_u8(vreinterpretq_u8_s8(rhs.reg), vdupq_n_u8(7));
+            Self {
+                reg: vshlq_s8(self.reg, vnegq_s8(vreinterpretq_s8_u8(n))),
+            }
+        }
+    }
+}
+
+/// vector operator >>= : shift right arithmetic by variable counts
+impl ops::ShrAssign<Vec16c> for Vec16c {
+    #[inline]
+    fn shr_assign(&mut self, rhs: Vec16c) {
+        *self = *self >> rhs
+    }
+}
+
+impl Vec16c {
+    /// Rotate each element left by the count in the corresponding element of b.
+    /// Counts are taken modulo 8, so negative counts rotate right
+    #[inline]
+    pub fn rotate_left(self, b: Vec16c) -> Vec16c {
+        Vec16c {
+            reg: rotate_left_u8(self.reg, unsafe { vandq_s8(b.reg, vdupq_n_s8(7)) }),
+        }
+    }
+
+    /// Rotate each element right by the count in the corresponding element of b.
+    /// Counts are taken modulo 8, so negative counts rotate left
+    #[inline]
+    pub fn rotate_right(self, b: Vec16c) -> Vec16c {
+        self.rotate_left(-b)
+    }
+}
+
+/// function popcount: count the number of 1-bits in each element
+#[inline]
+pub fn popcount(a: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vcntq_s8(a.reg) },
+    }
+}
+
+/// function leading_zeros: number of leading zero bits in each element. Gives 8 for a zero element
+#[inline]
+pub fn leading_zeros(a: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vclzq_s8(a.reg) },
+    }
+}
+
+/// function trailing_zeros: number of trailing zero bits in each element. Gives 8 for a zero element
+#[inline]
+pub fn trailing_zeros(a: Vec16c) -> Vec16c {
+    unsafe {
+        // ~a & (a - 1) has a 1-bit for each trailing zero of a
+        let am1 = vsubq_s8(a.reg, vdupq_n_s8(1));
+        Vec16c {
+            reg: vcntq_s8(vbicq_s8(am1, a.reg)),
+        }
+    }
+}
+
+/// function bit_reverse: reverse the order of the bits in each element
+#[inline]
+pub fn bit_reverse(a: Vec16c) -> Vec16c {
+    Vec16c {
+        reg: unsafe { vrbitq_s8(a.reg) },
+    }
+}
+
+/// function lookup16: table lookup with 16 entries.
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 16; i++) result[i] = table[index[i] & 15];
+#[inline]
+pub fn lookup16(index: Vec16c, table: Vec16c) -> Vec16c {
+    unsafe {
+        let index = vandq_u8(vreinterpretq_u8_s8(index.reg), vdupq_n_u8(0x0F));
+        Vec16c {
+            reg: vqtbl1q_s8(table.reg, index),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "vectori128_tests.rs"]
+mod tests;
@@ -0,0 +1,1059 @@
+//! Portable implementation of the 128-bit vector classes with plain arrays and element loops.
+//! It has the same interface and results as the SSE version, and is used when neither SSE nor
+//! NEON is available or the build sets --cfg vec_lib_scalar
+
+use core::hash::{Hash, Hasher};
+use core::ops;
+
+#[derive(Copy, Clone)]
+pub struct Vec128b {
+    bytes: [u8; 16],
+}
+
+impl Vec128b {
+    pub const LEN: usize = 128;
+
+    /// All bits zero
+    pub const ZERO: Self = Self::from_array_const([0; 16]);
+
+    /// All bits one
+    pub const ONES: Self = Self::from_array_const([0xFF; 16]);
+
+    /// Constructor from an array of bytes that can be used in constant expressions
+    pub const fn from_array_const(x: [u8; 16]) -> Self {
+        Vec128b { bytes: x }
+    }
+
+    pub fn new() -> Self {
+        Vec128b { bytes: [0; 16] }
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Loads from unaligned array
+    #[inline]
+    pub unsafe fn load(&mut self, mem_addr: *const u8) {
+        self.bytes = core::ptr::read_unaligned(mem_addr as *const [u8; 16]);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// mem_addr must be aligned by 16
+    #[inline]
+    pub unsafe fn load_aligned(&mut self, mem_addr: *const u8) {
+        debug_assert!(mem_addr.align_offset(16) == 0);
+        self.load(mem_addr);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Stores into unaligned array
+    #[inline]
+    pub unsafe fn store(&self, mem_addr: *mut u8) {
+        core::ptr::write_unaligned(mem_addr as *mut [u8; 16], self.bytes);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// mem_addr must be aligned by 16
+    #[inline]
+    pub unsafe fn store_aligned(&self, mem_addr: *mut u8) {
+        debug_assert!(mem_addr.align_offset(16) == 0);
+        self.store(mem_addr);
+    }
+
+    /// Shift all bytes up by N positions, zero-filling the lowest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_up<const N: i32>(self) -> Self {
+        Vec128b {
+            bytes: shift_bytes_up_array::<N, u8>(self.bytes),
+        }
+    }
+
+    /// Shift all bytes down by N positions, zero-filling the highest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_down<const N: i32>(self) -> Self {
+        Vec128b {
+            bytes: shift_bytes_down_array::<N, u8>(self.bytes),
+        }
+    }
+
+    /// Extract 16 consecutive bytes starting at byte N from the 32-byte concatenation hi:lo,
+    /// lo being the lower half. N must be in 0..=16
+    #[inline]
+    pub fn align_right<const N: i32>(hi: Self, lo: Self) -> Self {
+        Vec128b {
+            bytes: align_right_array::<N, u8>(hi.bytes, lo.bytes),
+        }
+    }
+}
+
+impl Default for Vec128b {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for Vec128b {
+    fn eq(&self, other: &Self) -> bool {
+        Vec16c::from(*self) == Vec16c::from(*other)
+    }
+}
+
+impl Eq for Vec128b {}
+
+impl Hash for Vec128b {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Vec16c::from(*self).hash(state)
+    }
+}
+
+impl ops::BitAnd for Vec128b {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, other: Vec128b) -> Vec128b {
+        Vec128b {
+            bytes: core::array::from_fn(|i| self.bytes[i] & other.bytes[i]),
+        }
+    }
+}
+
+impl ops::BitOr for Vec128b {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, other: Vec128b) -> Vec128b {
+        Vec128b {
+            bytes: core::array::from_fn(|i| self.bytes[i] | other.bytes[i]),
+        }
+    }
+}
+
+impl ops::BitXor for Vec128b {
+    type Output = Self;
+
+    #[inline]
+    fn bitxor(self, other: Vec128b) -> Vec128b {
+        Vec128b {
+            bytes: core::array::from_fn(|i| self.bytes[i] ^ other.bytes[i]),
+        }
+    }
+}
+
+impl ops::Not for Vec128b {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Vec128b {
+        Vec128b {
+            bytes: self.bytes.map(|x| !x),
+        }
+    }
+}
+
+impl ops::BitAndAssign for Vec128b {
+    #[inline]
+    fn bitand_assign(&mut self, other: Vec128b) {
+        *self = *self & other
+    }
+}
+
+impl ops::BitOrAssign for Vec128b {
+    #[inline]
+    fn bitor_assign(&mut self, other: Vec128b) {
+        *self = *self | other
+    }
+}
+
+impl ops::BitXorAssign for Vec128b {
+    #[inline]
+    fn bitxor_assign(&mut self, other: Vec128b) {
+        *self = *self ^ other
+    }
+}
+
+/// function andnot: a & ~ b
+#[inline]
+pub fn andnot(a: Vec128b, b: Vec128b) -> Vec128b {
+    a & !b
+}
+
+/// Shift the array up by N elements (towards higher index), zero-filling the low elements
+#[inline]
+fn shift_bytes_up_array<const N: i32, T: Copy + Default>(a: [T; 16]) -> [T; 16] {
+    const { assert!(N >= 0 && N <= 16, "byte shift count must be in 0..=16") };
+    let n = N as usize;
+    core::array::from_fn(|i| if i < n { T::default() } else { a[i - n] })
+}
+
+/// Shift the array down by N elements (towards lower index), zero-filling the high elements
+#[inline]
+fn shift_bytes_down_array<const N: i32, T: Copy + Default>(a: [T; 16]) -> [T; 16] {
+    const { assert!(N >= 0 && N <= 16, "byte shift count must be in 0..=16") };
+    let n = N as usize;
+    core::array::from_fn(|i| if i + n < 16 { a[i + n] } else { T::default() })
+}
+
+/// Concatenate hi:lo into 32 elements and extract the 16 elements starting at element N
+#[inline]
+fn align_right_array<const N: i32, T: Copy>(hi: [T; 16], lo: [T; 16]) -> [T; 16] {
+    const { assert!(N >= 0 && N <= 16, "alignment must be in 0..=16") };
+    let n = N as usize;
+    core::array::from_fn(|i| {
+        if i + n < 16 {
+            lo[i + n]
+        } else {
+            hi[i + n - 16]
+        }
+    })
+}
+
+/// Returns false if at least one bit is 0
+#[inline]
+pub fn horizontal_and(a: Vec128b) -> bool {
+    a.bytes.iter().all(|&x| x == 0xFF)
+}
+
+/// Returns true if at least one bit is 1
+#[inline]
+pub fn horizontal_or(a: Vec128b) -> bool {
+    a.bytes.iter().any(|&x| x != 0)
+}
+
+#[derive(Copy, Clone)]
+pub struct Vec16c {
+    pub(crate) elems: [i8; 16],
+}
+
+impl Vec16c {
+    pub const LEN: usize = 16;
+
+    /// All elements zero
+    pub const ZERO: Self = Self::from_array_const([0; 16]);
+
+    /// All bits one, i.e. all elements -1
+    pub const ONES: Self = Self::from_array_const([-1; 16]);
+
+    /// Constructor from an array that can be used in constant expressions
+    pub const fn from_array_const(x: [i8; 16]) -> Self {
+        Vec16c { elems: x }
+    }
+
+    pub fn new() -> Self {
+        Vec16c { elems: [0; 16] }
+    }
+
+    /// Constructor to broadcast the same value into all elements:
+    pub fn set_value(a: i8) -> Self {
+        Vec16c { elems: [a; 16] }
+    }
+
+    /// Constructor to build from all elements:
+    pub fn set_values(a: [i8; 16]) -> Self {
+        Vec16c { elems: a }
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Member function to load from array (unaligned)
+    #[inline]
+    pub unsafe fn load(&mut self, mem_addr: *const i8) {
+        self.elems = core::ptr::read_unaligned(mem_addr as *const [i8; 16]);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// mem_addr must be aligned by 16
+    #[inline]
+    pub unsafe fn load_aligned(&mut self, mem_addr: *const i8) {
+        debug_assert!(mem_addr.align_offset(16) == 0);
+        self.load(mem_addr);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Stores into unaligned array
+    #[inline]
+    pub unsafe fn store(&self, mem_addr: *mut i8) {
+        core::ptr::write_unaligned(mem_addr as *mut [i8; 16], self.elems);
+    }
+
+    ///# Safety: obvious
+    ///
+    /// mem_addr must be aligned by 16
+    #[inline]
+    pub unsafe fn store_aligned(&self, mem_addr: *mut i8) {
+        debug_assert!(mem_addr.align_offset(16) == 0);
+        self.store(mem_addr);
+    }
+
+    ///# Safety: n bytes in the mem_addr must be valid
+    ///
+    /// Partial load. Load n elements and set the rest to 0
+    #[inline]
+    pub unsafe fn load_partial(&mut self, n: usize, arr: &[i8]) {
+        let n = n.min(16);
+        self.elems = [0; 16];
+        self.elems[..n].copy_from_slice(&arr[..n]);
+    }
+
+    ///# Safety: at least n bytes must be allocated to the mem_addr
+    ///
+    /// Partial store. Store n elements
+    #[inline]
+    pub unsafe fn store_partial(&mut self, n: usize, mem_addr: *mut i8) {
+        for i in 0..n.min(16) {
+            *mem_addr.add(i) = self.elems[i];
+        }
+    }
+
+    ///# Safety: obvious
+    ///
+    /// cut off vector to n elements. The last 16-n elements are set to zero
+    #[inline]
+    pub unsafe fn cutoff(&mut self, n: usize) {
+        for x in self.elems.iter_mut().skip(n) {
+            *x = 0;
+        }
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Member function to change a single element in vector
+    #[inline]
+    pub unsafe fn insert(&mut self, index: isize, value: i8) {
+        self.elems[(index & 0x0F) as usize] = value;
+    }
+
+    ///# Safety: obvious
+    ///
+    /// Member function extract a single element from vector
+    #[inline]
+    pub unsafe fn extract(&mut self, index: usize) -> i8 {
+        self.elems[index & 0x0F]
+    }
+
+    /// Shift all bytes up by N positions, zero-filling the lowest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_up<const N: i32>(self) -> Self {
+        Vec16c {
+            elems: shift_bytes_up_array::<N, i8>(self.elems),
+        }
+    }
+
+    /// Shift all bytes down by N positions, zero-filling the highest N bytes. N must be in 0..=16
+    #[inline]
+    pub fn shift_bytes_down<const N: i32>(self) -> Self {
+        Vec16c {
+            elems: shift_bytes_down_array::<N, i8>(self.elems),
+        }
+    }
+
+    /// Extract 16 consecutive bytes starting at byte N from the 32-byte concatenation hi:lo,
+    /// lo being the lower half. N must be in 0..=16
+    #[inline]
+    pub fn align_right<const N: i32>(hi: Self, lo: Self) -> Self {
+        Vec16c {
+            elems: align_right_array::<N, i8>(hi.elems, lo.elems),
+        }
+    }
+
+    /// Apply f to each element
+    #[inline]
+    fn map(self, f: impl Fn(i8) -> i8) -> Self {
+        Vec16c {
+            elems: self.elems.map(f),
+        }
+    }
+
+    /// Apply f to each pair of elements
+    #[inline]
+    fn zip(self, other: Self, f: impl Fn(i8, i8) -> i8) -> Self {
+        Vec16c {
+            elems: core::array::from_fn(|i| f(self.elems[i], other.elems[i])),
+        }
+    }
+}
+
+impl Default for Vec16c {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Vec16bc {
+    elems: [i8; 16],
+}
+
+impl Vec16bc {
+    /// All elements false
+    pub const ZERO: Self = Self::from_array_const([false; 16]);
+
+    /// All elements true
+    pub const ONES: Self = Self::from_array_const([true; 16]);
+
+    /// Constructor from an array that can be used in constant expressions
+    pub const fn from_array_const(x: [bool; 16]) -> Self {
+        let mut y = [0i8; 16];
+        let mut i = 0;
+        while i < 16 {
+            y[i] = -(x[i] as i8);
+            i += 1;
+        }
+        Vec16bc { elems: y }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_values(
+        x0: bool,
+        x1: bool,
+        x2: bool,
+        x3: bool,
+        x4: bool,
+        x5: bool,
+        x6: bool,
+        x7: bool,
+        x8: bool,
+        x9: bool,
+        x10: bool,
+        x11: bool,
+        x12: bool,
+        x13: bool,
+        x14: bool,
+        x15: bool,
+    ) -> Self {
+        let x = [
+            x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, x12, x13, x14, x15,
+        ];
+        Vec16bc {
+            elems: x.map(|b| -(b as i8)),
+        }
+    }
+
+    /// Each element must be either 0 (false) or -1 (true)
+    pub fn set_value(x: [i8; 16]) -> Self {
+        Vec16bc { elems: x }
+    }
+}
+
+impl Default for Vec16bc {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl PartialEq for Vec16bc {
+    fn eq(&self, other: &Self) -> bool {
+        Vec16c::from(*self) == Vec16c::from(*other)
+    }
+}
+
+impl Eq for Vec16bc {}
+
+impl Hash for Vec16bc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Vec16c::from(*self).hash(state)
+    }
+}
+
+/// Get the bits of a boolean vector as an integer, bit i corresponding to element i
+#[inline]
+pub fn to_bits(x: Vec16bc) -> u16 {
+    (0..16).fold(0, |bits, i| bits | ((x.elems[i] < 0) as u16) << i)
+}
+
+/// Horizontal find first: index of the first true element, or None if all are false
+#[inline]
+pub fn horizontal_find_first(x: Vec16bc) -> Option<usize> {
+    let bits = to_bits(x);
+    if bits == 0 {
+        None
+    } else {
+        Some(bits.trailing_zeros() as usize)
+    }
+}
+
+/// Horizontal count: number of true elements
+#[inline]
+pub fn horizontal_count(x: Vec16bc) -> u32 {
+    to_bits(x).count_ones()
+}
+
+/// vector operator & : and of boolean vectors
+impl ops::BitAnd for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, other: Self) -> Self {
+        Vec16bc {
+            elems: core::array::from_fn(|i| self.elems[i] & other.elems[i]),
+        }
+    }
+}
+
+/// vector operator | : or of boolean vectors
+impl ops::BitOr for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, other: Self) -> Self {
+        Vec16bc {
+            elems: core::array::from_fn(|i| self.elems[i] | other.elems[i]),
+        }
+    }
+}
+
+/// vector operator ^ : xor of boolean vectors
+impl ops::BitXor for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn bitxor(self, other: Self) -> Self {
+        Vec16bc {
+            elems: core::array::from_fn(|i| self.elems[i] ^ other.elems[i]),
+        }
+    }
+}
+
+/// vector operator ! : not of a boolean vector
+impl ops::Not for Vec16bc {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Self {
+        Vec16bc {
+            elems: self.elems.map(|x| !x),
+        }
+    }
+}
+
+/*****************************************************************************
+*
+*          Define operators for Vec16c
+*
+*****************************************************************************/
+
+/// Convert Vec16bc to Vec16c
+impl From<Vec16bc> for Vec16c {
+    fn from(a: Vec16bc) -> Self {
+        Vec16c { elems: a.elems }
+    }
+}
+
+// Conversions between vectors of the same size keep all the bits, byte i being element i
+
+/// Reinterpret the 128 bits as 16 elements of 8 bits
+impl From<Vec128b> for Vec16c {
+    #[inline]
+    fn from(a: Vec128b) -> Self {
+        Vec16c {
+            elems: a.bytes.map(|x| x as i8),
+        }
+    }
+}
+
+/// Reinterpret the 16 elements as 128 bits
+impl From<Vec16c> for Vec128b {
+    #[inline]
+    fn from(a: Vec16c) -> Self {
+        Vec128b {
+            bytes: a.elems.map(|x| x as u8),
+        }
+    }
+}
+
+/// Reinterpret the 16 boolean elements as 128 bits
+impl From<Vec16bc> for Vec128b {
+    #[inline]
+    fn from(a: Vec16bc) -> Self {
+        Vec128b {
+            bytes: a.elems.map(|x| x as u8),
+        }
+    }
+}
+
+/// vector operator + : add element by element
+impl ops::Add for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        self.zip(other, i8::wrapping_add)
+    }
+}
+
+/// vector operator += : add
+impl ops::AddAssign for Vec16c {
+    #[inline]
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other
+    }
+}
+
+/// vector operator - : subtract element by element
+impl ops::Sub for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, other: Self) -> Self {
+        self.zip(other, i8::wrapping_sub)
+    }
+}
+
+/// vector operator - : unary minus
+impl ops::Neg for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        self.map(i8::wrapping_neg)
+    }
+}
+
+///vector operator -= : add
+impl ops::SubAssign for Vec16c {
+    #[inline]
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other
+    }
+}
+
+/// vector operator * : multiply element by element
+impl ops::Mul for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, other: Self) -> Self {
+        self.zip(other, i8::wrapping_mul)
+    }
+}
+
+/// vector operator *= : multiply
+impl ops::MulAssign for Vec16c {
+    #[inline]
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other
+    }
+}
+
+/// vector operator << : shift left all elements
+impl ops::Shl<i32> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shl(self, rhs: i32) -> Self::Output {
+        self.map(|x| if (rhs as u32) < 8 { x << rhs } else { 0 })
+    }
+}
+
+/// vector operator <<= : shift left
+impl ops::ShlAssign<i32> for Vec16c {
+    #[inline]
+    fn shl_assign(&mut self, rhs: i32) {
+        *self = *self << rhs
+    }
+}
+
+/// vector operator >> : shift right arithmetic all elements
+impl ops::Shr<i32> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shr(self, rhs: i32) -> Self::Output {
+        self.map(|x| x >> (rhs as u32).min(7))
+    }
+}
+
+/// vector operator >>= : shift right arithmetic
+impl ops::ShrAssign<i32> for Vec16c {
+    #[inline]
+    fn shr_assign(&mut self, rhs: i32) {
+        *self = *self >> rhs
+    }
+}
+
+/// vector operator & : bitwise and
+impl ops::BitAnd for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, other: Self) -> Self {
+        self.zip(other, |a, b| a & b)
+    }
+}
+
+/// vector operator &= : bitwise and
+impl ops::BitAndAssign for Vec16c {
+    #[inline]
+    fn bitand_assign(&mut self, other: Self) {
+        *self = *self & other
+    }
+}
+
+/// vector operator == : equality
+impl PartialEq for Vec16c {
+    fn eq(&self, other: &Self) -> bool {
+        self.elems == other.elems
+    }
+}
+
+impl Eq for Vec16c {}
+
+/// Hash of the elements, in agreement with ==
+impl Hash for Vec16c {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_array().hash(state)
+    }
+}
+
+/// function compare_eq: a[i] == b[i] for each element
+#[inline]
+pub fn compare_eq(a: Vec16c, b: Vec16c) -> Vec16bc {
+    compare_by(a, b, |x, y| x == y)
+}
+
+/// function compare_gt: a[i] > b[i] for each element, signed
+#[inline]
+pub fn compare_gt(a: Vec16c, b: Vec16c) -> Vec16bc {
+    compare_by(a, b, |x, y| x > y)
+}
+
+/// function compare_lt: a[i] < b[i] for each element, signed
+#[inline]
+pub fn compare_lt(a: Vec16c, b: Vec16c) -> Vec16bc {
+    compare_by(a, b, |x, y| x < y)
+}
+
+/// Boolean vector of f applied to each pair of elements
+#[inline]
+fn compare_by(a: Vec16c, b: Vec16c, f: impl Fn(i8, i8) -> bool) -> Vec16bc {
+    Vec16bc {
+        elems: core::array::from_fn(|i| -(f(a.elems[i], b.elems[i]) as i8)),
+    }
+}
+
+/// Select between two operands. Each element of s must be either 0 (false) or -1 (true).
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 16; i++) result[i] = s[i] ? a[i] : b[i];
+#[inline]
+pub fn select(s: Vec16bc, a: Vec16c, b: Vec16c) -> Vec16c {
+    Vec16c {
+        elems: core::array::from_fn(|i| (s.elems[i] & a.elems[i]) | (!s.elems[i] & b.elems[i])),
+    }
+}
+
+/// Conditional add: For all vector elements i: result[i] = f[i] ? (a[i] + b[i]) : a[i]
+#[inline]
+pub fn if_add(f: Vec16bc, a: Vec16c, b: Vec16c) -> Vec16c {
+    a + ((Vec16c::from(f)) & b)
+}
+
+/// Conditional sub: For all vector elements i: result[i] = f[i] ? (a[i] - b[i]) : a[i]
+#[inline]
+pub fn if_sub(f: Vec16bc, a: Vec16c, b: Vec16c) -> Vec16c {
+    a - ((Vec16c::from(f)) & b)
+}
+
+/// Conditional mul: For all vector elements i: result[i] = f[i] ? (a[i] * b[i]) : a[i]
+#[inline]
+pub fn if_mul(f: Vec16bc, a: Vec16c, b: Vec16c) -> Vec16c {
+    select(f, a * b, a)
+}
+
+/// Horizontal add: Calculates the sum of all vector elements. Overflow will wrap around
+#[inline]
+pub fn horizontal_add(a: Vec16c) -> i32 {
+    a.elems.iter().fold(0i8, |s, &x| s.wrapping_add(x)) as i32
+}
+
+/// Horizontal add extended: Calculates the sum of all vector elements.
+/// Each element is sign-extended before addition to avoid overflow
+#[inline]
+pub fn horizontal_add_x(a: Vec16c) -> i32 {
+    a.elems.iter().map(|&x| x as i32).sum()
+}
+
+/// Horizontal min: Smallest element of the vector
+#[inline]
+pub fn horizontal_min(a: Vec16c) -> i8 {
+    a.elems.into_iter().min().unwrap()
+}
+
+/// Horizontal max: Largest element of the vector
+#[inline]
+pub fn horizontal_max(a: Vec16c) -> i8 {
+    a.elems.into_iter().max().unwrap()
+}
+
+/// function add_saturated: add element by element, signed with saturatio
+#[inline]
+pub fn add_saturated(a: Vec16c, b: Vec16c) -> Vec16c {
+    a.zip(b, i8::saturating_add)
+}
+
+/// function sub_saturated: subtract element by element, signed with saturation
+#[inline]
+pub fn sub_saturated(a: Vec16c, b: Vec16c) -> Vec16c {
+    a.zip(b, i8::saturating_sub)
+}
+
+/// function max: a > b ? a : b
+#[inline]
+pub fn max(a: Vec16c, b: Vec16c) -> Vec16c {
+    a.zip(b, Ord::max)
+}
+
+/// function min: a < b ? a : b
+#[inline]
+pub fn min(a: Vec16c, b: Vec16c) -> Vec16c {
+    a.zip(b, Ord::min)
+}
+
+/// function abs: a >= 0 ? a : -a
+#[inline]
+pub fn abs(a: Vec16c) -> Vec16c {
+    a.map(i8::wrapping_abs)
+}
+
+/// function abs_saturated: same as abs, saturate if overflow
+#[inline]
+pub fn abs_saturated(a: Vec16c) -> Vec16c {
+    a.map(i8::saturating_abs)
+}
+
+/// function avg: (a + b + 1) >> 1, computed without overflow
+#[inline]
+pub fn avg(a: Vec16c, b: Vec16c) -> Vec16c {
+    a.zip(b, |x, y| ((x as i16 + y as i16 + 1) >> 1) as i8)
+}
+
+/// function abs_diff: |a - b| computed without overflow.
+/// The result is in 0..=255, so it is returned as unsigned bytes in Vec128b, as from abs_diff_u8
+#[inline]
+pub fn abs_diff(a: Vec16c, b: Vec16c) -> Vec128b {
+    Vec128b {
+        bytes: core::array::from_fn(|i| a.elems[i].abs_diff(b.elems[i])),
+    }
+}
+
+/// function sad: sum of absolute differences.
+/// Returns the sum of |a[i] - b[i]| over elements 0..8 and over elements 8..16
+#[inline]
+pub fn sad(a: Vec16c, b: Vec16c) -> [u64; 2] {
+    let sum = |r: core::ops::Range<usize>| r.map(|i| a.elems[i].abs_diff(b.elems[i]) as u64).sum();
+    [sum(0..8), sum(8..16)]
+}
+
+/// function mpsad: multiple sums of absolute differences (mpsadbw), for block matching.
+/// Compares the 4-element block of b starting at 4 * (IMM8 & 3) with the eight 4-element windows
+/// of a starting at elements 4 * ((IMM8 >> 2) & 1) + i. Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = sum(j = 0..4, |a[aoff + i + j] - b[boff + j]|);
+#[inline]
+pub fn mpsad<const IMM8: i32>(a: Vec16c, b: Vec16c) -> [u16; 8] {
+    const {
+        assert!(
+            IMM8 >= 0 && IMM8 <= 7,
+            "mpsad offset selector must be in 0..=7"
+        )
+    };
+    let aoff = 4 * ((IMM8 as usize >> 2) & 1);
+    let boff = 4 * (IMM8 as usize & 3);
+    core::array::from_fn(|i| {
+        (0..4)
+            .map(|j| a.elems[aoff + i + j].abs_diff(b.elems[boff + j]) as u16)
+            .sum()
+    })
+}
+
+// Unsigned versions for u8 data such as pixels. The elements of the Vec128b operands are read
+// as u8, as by Vec128b::to_array
+
+/// function avg_u8: (a + b + 1) >> 1 for unsigned elements, computed without overflow
+#[inline]
+pub fn avg_u8(a: Vec128b, b: Vec128b) -> Vec128b {
+    Vec128b {
+        bytes: core::array::from_fn(|i| ((a.bytes[i] as u16 + b.bytes[i] as u16 + 1) >> 1) as u8),
+    }
+}
+
+/// function abs_diff_u8: |a - b| for unsigned elements
+#[inline]
+pub fn abs_diff_u8(a: Vec128b, b: Vec128b) -> Vec128b {
+    Vec128b {
+        bytes: core::array::from_fn(|i| a.bytes[i].abs_diff(b.bytes[i])),
+    }
+}
+
+/// function sad_u8: sum of absolute differences of unsigned elements.
+/// Returns the sum of |a[i] - b[i]| over elements 0..8 and over elements 8..16
+#[inline]
+pub fn sad_u8(a: Vec128b, b: Vec128b) -> [u64; 2] {
+    let sum = |r: core::ops::Range<usize>| r.map(|i| a.bytes[i].abs_diff(b.bytes[i]) as u64).sum();
+    [sum(0..8), sum(8..16)]
+}
+
+/// function mpsad_u8: multiple sums of absolute differences of unsigned elements (mpsadbw).
+/// The blocks are selected by IMM8 as in mpsad
+#[inline]
+pub fn mpsad_u8<const IMM8: i32>(a: Vec128b, b: Vec128b) -> [u16; 8] {
+    const {
+        assert!(
+            IMM8 >= 0 && IMM8 <= 7,
+            "mpsad offset selector must be in 0..=7"
+        )
+    };
+    let (xa, xb) = (a.to_array(), b.to_array());
+    let aoff = 4 * ((IMM8 as usize >> 2) & 1);
+    let boff = 4 * (IMM8 as usize & 3);
+    core::array::from_fn(|i| {
+        (0..4)
+            .map(|j| xa[aoff + i + j].abs_diff(xb[boff + j]) as u16)
+            .sum()
+    })
+}
+
+/// function mul_hi: high 8 bits of the 16-bit product of each pair of elements, (a * b) >> 8
+#[inline]
+pub fn mul_hi(a: Vec16c, b: Vec16c) -> Vec16c {
+    a.zip(b, |x, y| ((x as i16 * y as i16) >> 8) as i8)
+}
+
+/// function mul_wide: full 16-bit products of all elements.
+/// Returns the products of elements 0..8 and of elements 8..16
+#[inline]
+pub fn mul_wide(a: Vec16c, b: Vec16c) -> ([i16; 8], [i16; 8]) {
+    let product = |i: usize| a.elems[i] as i16 * b.elems[i] as i16;
+    (
+        core::array::from_fn(product),
+        core::array::from_fn(|i| product(i + 8)),
+    )
+}
+
+/// function madd_u8i8: products of the unsigned elements of a and the signed elements of b,
+/// added in pairs with signed saturation (pmaddubsw). Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = saturate_i16(a[2i] * b[2i] + a[2i+1] * b[2i+1]);
+#[inline]
+pub fn madd_u8i8(a: Vec128b, b: Vec16c) -> [i16; 8] {
+    let product = |i: usize| a.bytes[i] as i32 * b.elems[i] as i32;
+    core::array::from_fn(|i| {
+        (product(2 * i) + product(2 * i + 1)).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+    })
+}
+
+/// function madd_i16: products of signed 16-bit elements added in pairs (pmaddwd).
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 4; i++) result[i] = a[2i] * b[2i] + a[2i+1] * b[2i+1];
+/// Only -32768 * -32768 + -32768 * -32768 overflows, and wraps around to i32::MIN
+#[inline]
+pub fn madd_i16(a: [i16; 8], b: [i16; 8]) -> [i32; 4] {
+    let product = |i: usize| a[i] as i32 * b[i] as i32;
+    core::array::from_fn(|i| product(2 * i).wrapping_add(product(2 * i + 1)))
+}
+
+/// function mul_hrs: high half of the product of signed 16-bit elements in fixed point, rounded
+/// (pmulhrsw). Corresponds to this pseudocode:
+/// for (int i = 0; i < 8; i++) result[i] = (a[i] * b[i] + 0x4000) >> 15;
+/// Only -32768 * -32768 overflows, and wraps around to -32768
+#[inline]
+pub fn mul_hrs(a: [i16; 8], b: [i16; 8]) -> [i16; 8] {
+    core::array::from_fn(|i| ((a[i] as i32 * b[i] as i32 + 0x4000) >> 15) as i16)
+}
+
+/// function rotate_left: rotate each element left by b bits
+/// Use negative count to rotate right
+#[inline]
+pub fn rotate_left(a: Vec16c, b: i32) -> Vec16c {
+    a.map(|x| (x as u8).rotate_left((b & 7) as u32) as i8)
+}
+
+/// vector operator << : shift left each element by the count in the corresponding element of rhs.
+/// Counts outside 0..=7 (negative counts included) give 0
+impl ops::Shl<Vec16c> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shl(self, rhs: Vec16c) -> Self::Output {
+        self.zip(rhs, |x, n| if (n as u8) < 8 { x << n } else { 0 })
+    }
+}
+
+/// vector operator <<= : shift left by variable counts
+impl ops::ShlAssign<Vec16c> for Vec16c {
+    #[inline]
+    fn shl_assign(&mut self, rhs: Vec16c) {
+        *self = *self << rhs
+    }
+}
+
+/// vector operator >> : shift right arithmetic each element by the count in the corresponding
+/// element of rhs. Counts outside 0..=7 (negative counts included) fill the element with its sign bit
+impl ops::Shr<Vec16c> for Vec16c {
+    type Output = Self;
+
+    #[inline]
+    fn shr(self, rhs: Vec16c) -> Self::Output {
+        self.zip(rhs, |x, n| x >> (n as u8).min(7))
+    }
+}
+
+/// vector operator >>= : shift right arithmetic by variable counts
+impl ops::ShrAssign<Vec16c> for Vec16c {
+    #[inline]
+    fn shr_assign(&mut self, rhs: Vec16c) {
+        *self = *self >> rhs
+    }
+}
+
+impl Vec16c {
+    /// Rotate each element left by the count in the corresponding element of b.
+    /// Counts are taken modulo 8, so negative counts rotate right
+    #[inline]
+    pub fn rotate_left(self, b: Vec16c) -> Vec16c {
+        self.zip(b, |x, n| (x as u8).rotate_left((n & 7) as u32) as i8)
+    }
+
+    /// Rotate each element right by the count in the corresponding element of b.
+    /// Counts are taken modulo 8, so negative counts rotate left
+    #[inline]
+    pub fn rotate_right(self, b: Vec16c) -> Vec16c {
+        self.zip(b, |x, n| (x as u8).rotate_right((n & 7) as u32) as i8)
+    }
+}
+
+/// function popcount: count the number of 1-bits in each element
+#[inline]
+pub fn popcount(a: Vec16c) -> Vec16c {
+    a.map(|x| x.count_ones() as i8)
+}
+
+/// function leading_zeros: number of leading zero bits in each element. Gives 8 for a zero element
+#[inline]
+pub fn leading_zeros(a: Vec16c) -> Vec16c {
+    a.map(|x| x.leading_zeros() as i8)
+}
+
+/// function trailing_zeros: number of trailing zero bits in each element. Gives 8 for a zero element
+#[inline]
+pub fn trailing_zeros(a: Vec16c) -> Vec16c {
+    a.map(|x| x.trailing_zeros() as i8)
+}
+
+/// function bit_reverse: reverse the order of the bits in each element
+#[inline]
+pub fn bit_reverse(a: Vec16c) -> Vec16c {
+    a.map(i8::reverse_bits)
+}
+
+/// function lookup16: table lookup with 16 entries.
+/// Corresponds to this pseudocode:
+/// for (int i = 0; i < 16; i++) result[i] = table[index[i] & 15];
+#[inline]
+pub fn lookup16(index: Vec16c, table: Vec16c) -> Vec16c {
+    index.map(|i| table.elems[(i & 0x0F) as usize])
+}
+
+#[cfg(test)]
+#[path = "vectori128_tests.rs"]
+mod tests;
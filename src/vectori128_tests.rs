@@ -0,0 +1,550 @@
+use super::*;
+use rand::Rng;
+#[test]
+fn test_vec128b() {
+    unsafe {
+        let mut arr: [u8; 16] = [0; 16];
+        let mut a128 = Vec128b::new();
+        a128.store(&mut arr as *mut u8);
+        assert_eq!(false, horizontal_or(a128));
+
+        arr[0] = 1;
+        a128.load(&arr as *const u8);
+        assert_eq!(true, horizontal_or(a128));
+        assert_eq!(false, horizontal_and(a128));
+
+        a128 ^= a128;
+        assert_eq!(false, horizontal_or(a128));
+    }
+}
+
+#[test]
+fn test_vec16c() {
+    let mut rng = rand::thread_rng();
+
+    unsafe {
+        let mut a16 = Vec16c::new();
+        let arr1: [i8; 16] = core::array::from_fn(|_| rng.gen_range(0..50));
+        let b16 = Vec16c::set_values(arr1);
+        a16.load(&arr1 as *const [i8] as *const i8);
+        println!("{:?}, {:?}", a16, b16);
+        assert_eq!(a16, b16);
+        assert_eq!(a16 & b16, a16 & b16);
+    }
+}
+
+fn lanes(a: Vec16c) -> [i8; 16] {
+    let mut x: [i8; 16] = [0; 16];
+    unsafe { a.store(&mut x as *mut i8) };
+    x
+}
+
+#[test]
+fn test_vec16c_variable_shifts() {
+    // every value against every count, 16 counts at a time
+    for a in i8::MIN..=i8::MAX {
+        for c0 in (0..256).step_by(16) {
+            let counts: [i8; 16] = core::array::from_fn(|i| (c0 + i) as u8 as i8);
+            let av = Vec16c::set_value(a);
+            let cv = Vec16c::set_values(counts);
+            let shl = lanes(av << cv);
+            let shr = lanes(av >> cv);
+            let rol = lanes(av.rotate_left(cv));
+            let ror = lanes(av.rotate_right(cv));
+            for i in 0..16 {
+                let c = counts[i] as u8 as u32;
+                let expect_shl = if c < 8 { a.wrapping_shl(c) } else { 0 };
+                assert_eq!(shl[i], expect_shl, "{} << {}", a, c);
+                assert_eq!(shr[i], a >> c.min(7), "{} >> {}", a, c);
+                assert_eq!(rol[i], (a as u8).rotate_left(c) as i8, "{} rol {}", a, c);
+                assert_eq!(ror[i], (a as u8).rotate_right(c) as i8, "{} ror {}", a, c);
+            }
+        }
+    }
+}
+
+fn check_byte_shifts<const N: i32>() {
+    let lo: [i8; 16] = core::array::from_fn(|i| i as i8 + 1);
+    let hi: [i8; 16] = core::array::from_fn(|i| i as i8 + 17);
+    let cat: [i8; 32] = core::array::from_fn(|i| i as i8 + 1);
+    let n = N as usize;
+
+    let up = lanes(Vec16c::set_values(lo).shift_bytes_up::<N>());
+    let down = lanes(Vec16c::set_values(lo).shift_bytes_down::<N>());
+    let aligned = lanes(Vec16c::align_right::<N>(
+        Vec16c::set_values(hi),
+        Vec16c::set_values(lo),
+    ));
+    for i in 0..16 {
+        assert_eq!(up[i], if i < n { 0 } else { lo[i - n] }, "up {} [{}]", n, i);
+        assert_eq!(
+            down[i],
+            if i + n < 16 { lo[i + n] } else { 0 },
+            "down {} [{}]",
+            n,
+            i
+        );
+        assert_eq!(aligned[i], cat[i + n], "align {} [{}]", n, i);
+    }
+
+    let mut arr: [u8; 16] = [0; 16];
+    let mut b = Vec128b::new();
+    unsafe {
+        b.load(&lo as *const i8 as *const u8);
+        Vec128b::align_right::<N>(b, b.shift_bytes_up::<N>()).store(&mut arr as *mut u8);
+    }
+    for i in 0..16 {
+        let expect = if i + n < 16 { lo[i] } else { lo[i + n - 16] };
+        assert_eq!(arr[i] as i8, expect, "vec128b {} [{}]", n, i);
+    }
+}
+
+#[test]
+fn test_byte_shifts() {
+    check_byte_shifts::<0>();
+    check_byte_shifts::<1>();
+    check_byte_shifts::<2>();
+    check_byte_shifts::<3>();
+    check_byte_shifts::<4>();
+    check_byte_shifts::<5>();
+    check_byte_shifts::<7>();
+    check_byte_shifts::<8>();
+    check_byte_shifts::<9>();
+    check_byte_shifts::<12>();
+    check_byte_shifts::<15>();
+    check_byte_shifts::<16>();
+}
+
+#[test]
+fn test_vec16c_bit_counting() {
+    // exhaustive over all 256 byte values
+    for a0 in (0..256).step_by(16) {
+        let arr: [i8; 16] = core::array::from_fn(|i| (a0 + i) as u8 as i8);
+        let a = Vec16c::set_values(arr);
+        let pop = lanes(popcount(a));
+        let lz = lanes(leading_zeros(a));
+        let tz = lanes(trailing_zeros(a));
+        let rev = lanes(bit_reverse(a));
+        for i in 0..16 {
+            let x = arr[i] as u8;
+            assert_eq!(pop[i] as u32, x.count_ones(), "popcount {:#x}", x);
+            assert_eq!(lz[i] as u32, x.leading_zeros(), "leading_zeros {:#x}", x);
+            assert_eq!(tz[i] as u32, x.trailing_zeros(), "trailing_zeros {:#x}", x);
+            assert_eq!(rev[i] as u8, x.reverse_bits(), "bit_reverse {:#x}", x);
+        }
+    }
+}
+
+#[test]
+fn test_vec16c_horizontal() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        let arr: [i8; 16] = core::array::from_fn(|_| rng.gen());
+        let a = Vec16c::set_values(arr);
+        let wrapped = arr.iter().fold(0i8, |s, &x| s.wrapping_add(x));
+        let extended: i32 = arr.iter().map(|&x| x as i32).sum();
+        assert_eq!(horizontal_add(a), wrapped as i32);
+        assert_eq!(horizontal_add_x(a), extended);
+        assert_eq!(horizontal_min(a), *arr.iter().min().unwrap());
+        assert_eq!(horizontal_max(a), *arr.iter().max().unwrap());
+    }
+    assert_eq!(horizontal_min(Vec16c::set_value(i8::MIN)), i8::MIN);
+    assert_eq!(horizontal_max(Vec16c::set_value(i8::MIN)), i8::MIN);
+    assert_eq!(horizontal_min(Vec16c::set_value(i8::MAX)), i8::MAX);
+    assert_eq!(horizontal_max(Vec16c::set_value(i8::MAX)), i8::MAX);
+    assert_eq!(horizontal_add_x(Vec16c::set_value(i8::MIN)), -2048);
+
+    for _ in 0..1000 {
+        let bools: [bool; 16] = core::array::from_fn(|_| rng.gen_bool(0.1));
+        let b = Vec16bc::set_values(
+            bools[0], bools[1], bools[2], bools[3], bools[4], bools[5], bools[6], bools[7],
+            bools[8], bools[9], bools[10], bools[11], bools[12], bools[13], bools[14], bools[15],
+        );
+        assert_eq!(horizontal_find_first(b), bools.iter().position(|&x| x));
+        assert_eq!(
+            horizontal_count(b),
+            bools.iter().filter(|&&x| x).count() as u32
+        );
+    }
+}
+
+fn check_mpsad<const IMM8: i32>(xa: &[i8; 16], xb: &[i8; 16]) {
+    let aoff = 4 * ((IMM8 as usize >> 2) & 1);
+    let boff = 4 * (IMM8 as usize & 3);
+    let expect: [u16; 8] = core::array::from_fn(|i| {
+        (0..4)
+            .map(|j| xa[aoff + i + j].abs_diff(xb[boff + j]) as u16)
+            .sum()
+    });
+    let a = Vec16c::set_values(*xa);
+    let b = Vec16c::set_values(*xb);
+    assert_eq!(mpsad::<IMM8>(a, b), expect, "mpsad {}", IMM8);
+}
+
+#[test]
+fn test_vec16c_avg_sad() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        let xa: [i8; 16] = core::array::from_fn(|_| rng.gen());
+        let xb: [i8; 16] = core::array::from_fn(|_| rng.gen());
+        let a = Vec16c::set_values(xa);
+        let b = Vec16c::set_values(xb);
+        let av = lanes(avg(a, b));
+        let ad = abs_diff(a, b).to_array();
+        for i in 0..16 {
+            let expect = (xa[i] as i32 + xb[i] as i32 + 1) >> 1;
+            assert_eq!(av[i] as i32, expect, "avg {} {}", xa[i], xb[i]);
+            assert_eq!(ad[i], xa[i].abs_diff(xb[i]), "abs_diff {} {}", xa[i], xb[i]);
+        }
+        let lo: u64 = (0..8).map(|i| xa[i].abs_diff(xb[i]) as u64).sum();
+        let hi: u64 = (8..16).map(|i| xa[i].abs_diff(xb[i]) as u64).sum();
+        assert_eq!(sad(a, b), [lo, hi]);
+
+        check_mpsad::<0>(&xa, &xb);
+        check_mpsad::<1>(&xa, &xb);
+        check_mpsad::<2>(&xa, &xb);
+        check_mpsad::<3>(&xa, &xb);
+        check_mpsad::<4>(&xa, &xb);
+        check_mpsad::<5>(&xa, &xb);
+        check_mpsad::<6>(&xa, &xb);
+        check_mpsad::<7>(&xa, &xb);
+    }
+    let min = Vec16c::set_value(i8::MIN);
+    let max = Vec16c::set_value(i8::MAX);
+    assert_eq!(abs_diff(min, max).to_array(), [255; 16]);
+    assert_eq!(abs_diff(max, min).to_array(), [255; 16]);
+    assert_eq!(abs_diff(min, min).to_array(), [0; 16]);
+    assert_eq!(
+        abs_diff(Vec16c::set_value(-1), Vec16c::set_value(1)).to_array(),
+        [2; 16]
+    );
+    assert_eq!(sad(min, max), [8 * 255, 8 * 255]);
+}
+
+fn check_mpsad_u8<const IMM8: i32>(xa: &[u8; 16], xb: &[u8; 16]) {
+    let aoff = 4 * ((IMM8 as usize >> 2) & 1);
+    let boff = 4 * (IMM8 as usize & 3);
+    let expect: [u16; 8] = core::array::from_fn(|i| {
+        (0..4)
+            .map(|j| xa[aoff + i + j].abs_diff(xb[boff + j]) as u16)
+            .sum()
+    });
+    let a = Vec128b::from_array(*xa);
+    let b = Vec128b::from_array(*xb);
+    assert_eq!(mpsad_u8::<IMM8>(a, b), expect, "mpsad_u8 {}", IMM8);
+}
+
+#[test]
+fn test_avg_sad_u8() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        let xa: [u8; 16] = rng.gen();
+        let xb: [u8; 16] = rng.gen();
+        let a = Vec128b::from_array(xa);
+        let b = Vec128b::from_array(xb);
+        let av = avg_u8(a, b).to_array();
+        let ad = abs_diff_u8(a, b).to_array();
+        for i in 0..16 {
+            let expect = (xa[i] as u16 + xb[i] as u16 + 1) >> 1;
+            assert_eq!(av[i] as u16, expect, "avg_u8 {} {}", xa[i], xb[i]);
+            assert_eq!(
+                ad[i],
+                xa[i].abs_diff(xb[i]),
+                "abs_diff_u8 {} {}",
+                xa[i],
+                xb[i]
+            );
+        }
+        let lo: u64 = (0..8).map(|i| xa[i].abs_diff(xb[i]) as u64).sum();
+        let hi: u64 = (8..16).map(|i| xa[i].abs_diff(xb[i]) as u64).sum();
+        assert_eq!(sad_u8(a, b), [lo, hi]);
+
+        check_mpsad_u8::<0>(&xa, &xb);
+        check_mpsad_u8::<1>(&xa, &xb);
+        check_mpsad_u8::<2>(&xa, &xb);
+        check_mpsad_u8::<3>(&xa, &xb);
+        check_mpsad_u8::<4>(&xa, &xb);
+        check_mpsad_u8::<5>(&xa, &xb);
+        check_mpsad_u8::<6>(&xa, &xb);
+        check_mpsad_u8::<7>(&xa, &xb);
+    }
+    // black and white pixels, where the signed functions differ
+    let black = Vec128b::from_array([0; 16]);
+    let white = Vec128b::from_array([255; 16]);
+    assert_eq!(avg_u8(black, white).to_array(), [128; 16]);
+    assert_eq!(abs_diff_u8(black, white).to_array(), [255; 16]);
+    assert_eq!(sad_u8(black, white), [8 * 255, 8 * 255]);
+    assert_eq!(mpsad_u8::<0>(white, black), [4 * 255; 8]);
+    let gray = Vec128b::from_array([100; 16]);
+    let light = Vec128b::from_array([200; 16]);
+    assert_eq!(avg_u8(gray, light).to_array(), [150; 16]);
+    assert_eq!(sad_u8(gray, light), [800, 800]);
+}
+
+#[test]
+fn test_vec16c_mul_hi_wide() {
+    // every value against every value, 16 at a time
+    for a in i8::MIN..=i8::MAX {
+        for b0 in (0..256).step_by(16) {
+            let xb: [i8; 16] = core::array::from_fn(|i| (b0 + i) as u8 as i8);
+            let av = Vec16c::set_value(a);
+            let bv = Vec16c::set_values(xb);
+            let hi = lanes(mul_hi(av, bv));
+            let (wlo, whi) = mul_wide(av, bv);
+            for i in 0..16 {
+                let p = a as i16 * xb[i] as i16;
+                assert_eq!(hi[i], (p >> 8) as i8, "mul_hi {} {}", a, xb[i]);
+                let w = if i < 8 { wlo[i] } else { whi[i - 8] };
+                assert_eq!(w, p, "mul_wide {} {}", a, xb[i]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_madd_mul_hrs() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        let xa: [u8; 16] = rng.gen();
+        let xb: [i8; 16] = rng.gen();
+        let expect: [i16; 8] = core::array::from_fn(|i| {
+            let sum =
+                xa[2 * i] as i32 * xb[2 * i] as i32 + xa[2 * i + 1] as i32 * xb[2 * i + 1] as i32;
+            sum.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+        });
+        let a = Vec128b::from_array(xa);
+        assert_eq!(madd_u8i8(a, Vec16c::set_values(xb)), expect, "madd_u8i8");
+
+        let wa: [i16; 8] = rng.gen();
+        let wb: [i16; 8] = rng.gen();
+        let expect: [i32; 4] = core::array::from_fn(|i| {
+            (wa[2 * i] as i32 * wb[2 * i] as i32)
+                .wrapping_add(wa[2 * i + 1] as i32 * wb[2 * i + 1] as i32)
+        });
+        assert_eq!(madd_i16(wa, wb), expect, "madd_i16");
+        let expect: [i16; 8] =
+            core::array::from_fn(|i| ((wa[i] as i32 * wb[i] as i32 + 0x4000) >> 15) as i16);
+        assert_eq!(mul_hrs(wa, wb), expect, "mul_hrs");
+    }
+
+    // 255 * 127 * 2 and 255 * -128 * 2 saturate
+    let a = Vec128b::from_array([255; 16]);
+    assert_eq!(madd_u8i8(a, Vec16c::set_value(127)), [i16::MAX; 8]);
+    assert_eq!(madd_u8i8(a, Vec16c::set_value(-128)), [i16::MIN; 8]);
+    let mixed = Vec16c::set_values([127, -128, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(madd_u8i8(a, mixed), [-255, 0, 510, 0, 0, 0, 0, 0]);
+
+    let min = [i16::MIN; 8];
+    assert_eq!(madd_i16(min, min), [i32::MIN; 4]);
+    assert_eq!(madd_i16([i16::MAX; 8], min), [-2 * 32767 * 32768; 4]);
+    assert_eq!(mul_hrs(min, min), min);
+    assert_eq!(mul_hrs([0x4000; 8], [0x4000; 8]), [0x2000; 8]);
+    assert_eq!(mul_hrs([1; 8], [0x4000; 8]), [1; 8]);
+    assert_eq!(mul_hrs([-1; 8], [0x4000; 8]), [0; 8]);
+}
+
+#[test]
+fn test_lookup16() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        let xi: [i8; 16] = core::array::from_fn(|_| rng.gen());
+        let xt: [i8; 16] = core::array::from_fn(|_| rng.gen());
+        let r = lanes(lookup16(Vec16c::set_values(xi), Vec16c::set_values(xt)));
+        for i in 0..16 {
+            assert_eq!(r[i], xt[(xi[i] & 0x0F) as usize]);
+        }
+    }
+}
+
+#[test]
+fn test_vec16c_and_conditional() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        let xa: [i8; 16] = rng.gen();
+        let xb: [i8; 16] = rng.gen();
+        let f: [bool; 16] = rng.gen();
+        let a = Vec16c::set_values(xa);
+        let b = Vec16c::set_values(xb);
+        let s = Vec16bc::set_values(
+            f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7], f[8], f[9], f[10], f[11], f[12], f[13],
+            f[14], f[15],
+        );
+        let mut c = a;
+        c &= b;
+        assert_eq!(c, a & b);
+        let and = lanes(a & b);
+        let add = lanes(if_add(s, a, b));
+        let sub = lanes(if_sub(s, a, b));
+        let mul = lanes(if_mul(s, a, b));
+        for i in 0..16 {
+            assert_eq!(and[i], xa[i] & xb[i]);
+            let pick = |r: i8| if f[i] { r } else { xa[i] };
+            assert_eq!(add[i], pick(xa[i].wrapping_add(xb[i])));
+            assert_eq!(sub[i], pick(xa[i].wrapping_sub(xb[i])));
+            assert_eq!(mul[i], pick(xa[i].wrapping_mul(xb[i])));
+        }
+    }
+}
+
+#[test]
+fn test_reinterpret() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..100 {
+        let x: [i8; 16] = rng.gen();
+        let a = Vec16c::set_values(x);
+        let b = Vec128b::from(a);
+        let mut bytes: [u8; 16] = [0; 16];
+        unsafe { b.store(&mut bytes as *mut u8) };
+        assert_eq!(bytes, x.map(|v| v as u8));
+        assert_eq!(Vec16c::from(b), a);
+        assert_eq!(Vec16c::from(!!b), a);
+
+        let f: [bool; 16] = rng.gen();
+        let s = Vec16bc::set_values(
+            f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7], f[8], f[9], f[10], f[11], f[12], f[13],
+            f[14], f[15],
+        );
+        assert_eq!(Vec16c::from(Vec128b::from(s)), Vec16c::from(s));
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(vec_lib_scalar)))]
+#[test]
+fn test_raw_register() {
+    use core::arch::x86_64::*;
+
+    let x: [i8; 16] = core::array::from_fn(|i| i as i8 - 8);
+    let a = Vec16c::set_values(x);
+    let raw: __m128i = a.into();
+    let sum = Vec16c::from(unsafe { _mm_add_epi8(raw, _mm_set1_epi8(1)) });
+    assert_eq!(lanes(sum), x.map(|v| v + 1));
+    assert_eq!(unsafe { _mm_extract_epi16::<0>(raw) }, 0xF9F8);
+
+    let b = Vec128b::from(raw);
+    assert_eq!(Vec16c::from(__m128i::from(b)), a);
+
+    let s = Vec16bc::from(unsafe { _mm_cmplt_epi8(raw, _mm_setzero_si128()) });
+    assert_eq!(to_bits(s), 0x00FF);
+    assert_eq!(Vec16c::from(__m128i::from(s)), Vec16c::from(s));
+
+    // the bytes of 1.5f32 and -2.0f64, little endian
+    let one_and_a_half = Vec16c::set_values(core::array::from_fn(|i| [0, 0, -64, 63][i % 4]));
+    let f: __m128 = Vec128b::from(one_and_a_half).into();
+    assert_eq!(unsafe { _mm_cvtss_f32(_mm_shuffle_ps::<3>(f, f)) }, 1.5);
+    assert_eq!(Vec16c::from(Vec128b::from(f)), one_and_a_half);
+    let d = unsafe { _mm_set_pd(-2.0, 0.0) };
+    let b = Vec128b::from(d);
+    assert_eq!(lanes(Vec16c::from(b))[8..], [0, 0, 0, 0, 0, 0, 0, -64]);
+    let d = __m128d::from(b);
+    assert_eq!(unsafe { _mm_cvtsd_f64(_mm_unpackhi_pd(d, d)) }, -2.0);
+}
+
+#[cfg(all(target_arch = "aarch64", not(vec_lib_scalar)))]
+#[test]
+fn test_raw_register() {
+    use core::arch::aarch64::*;
+
+    let x: [i8; 16] = core::array::from_fn(|i| i as i8 - 8);
+    let a = Vec16c::set_values(x);
+    let raw: int8x16_t = a.into();
+    let sum = Vec16c::from(unsafe { vaddq_s8(raw, vdupq_n_s8(1)) });
+    assert_eq!(lanes(sum), x.map(|v| v + 1));
+
+    let b = Vec128b::from(unsafe { vreinterpretq_u8_s8(raw) });
+    assert_eq!(Vec16c::from(b), a);
+
+    let s = Vec16bc::from(unsafe { vcltzq_s8(raw) });
+    assert_eq!(to_bits(s), 0x00FF);
+    assert_eq!(to_bits(Vec16bc::from(uint8x16_t::from(s))), 0x00FF);
+
+    // the bytes of 1.5f32 and -2.0f64, little endian
+    let one_and_a_half = Vec16c::set_values(core::array::from_fn(|i| [0, 0, -64, 63][i % 4]));
+    let f: float32x4_t = Vec128b::from(one_and_a_half).into();
+    assert_eq!(unsafe { vgetq_lane_f32::<3>(f) }, 1.5);
+    assert_eq!(Vec16c::from(Vec128b::from(f)), one_and_a_half);
+    let d = unsafe { vsetq_lane_f64::<1>(-2.0, vdupq_n_f64(0.0)) };
+    let b = Vec128b::from(d);
+    assert_eq!(lanes(Vec16c::from(b))[8..], [0, 0, 0, 0, 0, 0, 0, -64]);
+    assert_eq!(unsafe { vgetq_lane_f64::<1>(float64x2_t::from(b)) }, -2.0);
+}
+
+#[test]
+fn test_compare_and_mask_ops() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        // small values so that equal elements are common
+        let xa: [i8; 16] = core::array::from_fn(|_| rng.gen_range(-3..3));
+        let xb: [i8; 16] = core::array::from_fn(|_| rng.gen_range(-3..3));
+        let a = Vec16c::set_values(xa);
+        let b = Vec16c::set_values(xb);
+        let bits = |f: &dyn Fn(usize) -> bool| (0..16).fold(0u16, |m, i| m | (f(i) as u16) << i);
+        let eq = compare_eq(a, b);
+        let gt = compare_gt(a, b);
+        let lt = compare_lt(a, b);
+        assert_eq!(to_bits(eq), bits(&|i| xa[i] == xb[i]));
+        assert_eq!(to_bits(gt), bits(&|i| xa[i] > xb[i]));
+        assert_eq!(to_bits(lt), bits(&|i| xa[i] < xb[i]));
+        assert_eq!(to_bits(eq | gt), bits(&|i| xa[i] >= xb[i]));
+        assert_eq!(to_bits(!lt), bits(&|i| xa[i] >= xb[i]));
+        assert_eq!(to_bits(eq & gt), 0);
+        assert_eq!(to_bits(gt ^ lt), !to_bits(eq));
+    }
+    let min = Vec16c::set_value(i8::MIN);
+    let max = Vec16c::set_value(i8::MAX);
+    assert_eq!(to_bits(compare_gt(max, min)), 0xFFFF);
+    assert_eq!(to_bits(compare_lt(max, min)), 0);
+}
+
+#[test]
+fn test_const_default_eq_hash() {
+    use std::collections::HashSet;
+
+    static TABLE: [Vec16c; 2] = [
+        Vec16c::ZERO,
+        Vec16c::from_array_const([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
+    ];
+    const MASK: Vec16bc = Vec16bc::from_array_const([
+        true, false, true, false, true, false, true, false, true, false, true, false, true, false,
+        true, false,
+    ]);
+    const ONES: Vec128b = Vec128b::ONES;
+
+    assert_eq!(TABLE[0], Vec16c::set_value(0));
+    assert_eq!(TABLE[1], Vec16c::from_fn(|i| i as i8));
+    assert_eq!(Vec16c::ONES, Vec16c::set_value(-1));
+    assert_eq!(to_bits(MASK), 0x5555);
+    assert_eq!(to_bits(Vec16bc::ONES), 0xFFFF);
+    assert_eq!(to_bits(Vec16bc::ZERO), 0);
+    assert_eq!(ONES.to_array(), [0xFF; 16]);
+    assert_eq!(Vec128b::ZERO, Vec128b::new());
+
+    assert_eq!(Vec128b::default(), Vec128b::ZERO);
+    assert_eq!(Vec16c::default(), Vec16c::ZERO);
+    assert_eq!(Vec16bc::default(), Vec16bc::ZERO);
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        let a = Vec16c::from_fn(|_| rng.gen_range(-1..=1));
+        let b = Vec16c::from_fn(|_| rng.gen_range(-1..=1));
+        let equal = a.to_array() == b.to_array();
+        assert_eq!(a == b, equal);
+        assert_eq!(Vec128b::from(a) == Vec128b::from(b), equal);
+        let (ma, mb) = (compare_lt(a, Vec16c::ZERO), compare_lt(b, Vec16c::ZERO));
+        assert_eq!(ma == mb, to_bits(ma) == to_bits(mb));
+    }
+
+    let set: HashSet<Vec16c> = [TABLE[0], TABLE[1], Vec16c::new(), TABLE[1] + TABLE[0]]
+        .into_iter()
+        .collect();
+    assert_eq!(set.len(), 2);
+    let set: HashSet<Vec16bc> = [MASK, !MASK, !!MASK, Vec16bc::ZERO].into_iter().collect();
+    assert_eq!(set.len(), 3);
+}